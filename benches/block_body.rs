@@ -0,0 +1,87 @@
+// Copyright 2018 Althea Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serialization throughput on a block body with 10 000 transactions, and on
+//! deeply nested lists. Run with `cargo bench`.
+
+extern crate serde;
+extern crate serde_bytes;
+extern crate serde_rlp;
+#[macro_use]
+extern crate serde_derive;
+
+use serde::Serialize;
+use serde_bytes::ByteBuf;
+use serde_rlp::ser::to_bytes;
+use std::time::{Duration, Instant};
+
+#[derive(Serialize)]
+struct Transaction {
+    nonce: u64,
+    gas_price: u64,
+    gas_limit: u64,
+    to: ByteBuf,
+    value: u64,
+    data: ByteBuf,
+    v: u64,
+    r: ByteBuf,
+    s: ByteBuf,
+}
+
+#[derive(Serialize)]
+struct BlockBody {
+    transactions: Vec<Transaction>,
+    uncles: Vec<Transaction>,
+}
+
+fn block_body(transactions: u64) -> BlockBody {
+    BlockBody {
+        transactions: (0..transactions)
+            .map(|i| Transaction {
+                nonce: i,
+                gas_price: 20_000_000_000,
+                gas_limit: 21_000 + i,
+                to: ByteBuf::from(vec![0xaa; 20]),
+                value: 1_000_000_000_000_000_000 + i,
+                data: ByteBuf::from(vec![0x60; 68]),
+                v: 37,
+                r: ByteBuf::from(vec![0x11; 32]),
+                s: ByteBuf::from(vec![0x22; 32]),
+            })
+            .collect(),
+        uncles: Vec::new(),
+    }
+}
+
+#[derive(Serialize)]
+struct Nested(Vec<Nested>);
+
+fn nested(depth: usize) -> Nested {
+    (0..depth).fold(Nested(Vec::new()), |inner, _| Nested(vec![inner]))
+}
+
+fn bench<T: Serialize>(name: &str, value: &T, iterations: u32) {
+    let size = to_bytes(value).unwrap().len();
+    let start = Instant::now();
+    for _ in 0..iterations {
+        to_bytes(value).unwrap();
+    }
+    let elapsed = start.elapsed() / iterations;
+    println!(
+        "{:<24} {:>9} bytes {:>12.3?}/iter {:>9.1} MB/s",
+        name,
+        size,
+        elapsed,
+        size as f64 / elapsed.max(Duration::from_nanos(1)).as_secs_f64() / 1e6
+    );
+}
+
+fn main() {
+    bench("block body, 10k txs", &block_body(10_000), 50);
+    bench("nested lists, depth 1k", &nested(1_000), 50);
+}
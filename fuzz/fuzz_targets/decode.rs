@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use serde_rlp::de::from_bytes;
+use serde_rlp::value::RlpValue;
+
+fuzz_target!(|data: &[u8]| {
+    // Anything goes, as long as it doesn't panic
+    let _ = from_bytes::<RlpValue>(data);
+    let _ = from_bytes::<Vec<Vec<u8>>>(data);
+    let _ = from_bytes::<(u64, String, Vec<u64>)>(data);
+});
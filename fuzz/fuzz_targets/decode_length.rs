@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use serde_rlp::rlp::{check_canonical_length, decode_header, decode_length};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(res) = decode_length(data) {
+        // The whole item has to fit into the input
+        let end = res.offset.checked_add(res.length).unwrap();
+        assert!(end <= data.len());
+
+        let header = decode_header(data).unwrap();
+        assert_eq!((header.offset, header.length), (res.offset, res.length));
+        assert_eq!(header.expected_type, res.expected_type);
+
+        let _ = check_canonical_length(data, &res);
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use serde_rlp::de::from_bytes;
+use serde_rlp::ser::to_bytes;
+use serde_rlp::value::RlpValue;
+
+fuzz_target!(|data: &[u8]| {
+    let value = match from_bytes::<RlpValue>(data) {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+    let encoded = to_bytes(&value).unwrap();
+    // Strict decoding only accepts canonical input, which encodes back as is
+    assert_eq!(encoded, data);
+
+    let decoded = from_bytes::<RlpValue>(&encoded).unwrap();
+    assert_eq!(decoded, value);
+    assert_eq!(to_bytes(&decoded).unwrap(), encoded);
+});
@@ -0,0 +1,73 @@
+// Copyright 2018 Althea Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conventions for mapping Serde data types that have no counterpart in RLP.
+//!
+//! The same setting has to be used on both the `Serializer` and the
+//! `Deserializer`, otherwise encoded data won't decode back.
+
+/// Layout of a struct.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StructEncoding {
+    /// List of field values in declaration order. This is how Ethereum encodes
+    /// transactions, block headers and receipts.
+    #[default]
+    List,
+    /// List of `[key, value]` pairs, where key is the field name.
+    KeyValue,
+}
+
+/// Identifier of an enum variant.
+///
+/// Every enum variant is encoded as a list that starts with the tag, followed
+/// by the payload of the variant:
+///
+/// * unit variant `A` is `[tag]`
+/// * newtype variant `B(x)` is `[tag, x]`
+/// * tuple variant `C(x, y)` is `[tag, x, y]`
+/// * struct variant `D { x, y }` is `[tag, x, y]`, or `[tag, ["x", x], ["y", y]]`
+///   with `StructEncoding::KeyValue`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VariantTag {
+    /// Name of the variant as a string.
+    #[default]
+    Name,
+    /// Zero based index of the variant in declaration order, as an integer.
+    Index,
+}
+
+/// Representation of signed integers, which RLP does not define.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignedEncoding {
+    /// Signed integers are refused with `ErrorKind::UnsupportedType`.
+    #[default]
+    Reject,
+    /// Zigzag mapping to an unsigned integer: 0, -1, 1, -2 become 0, 1, 2, 3.
+    /// Small magnitudes of either sign stay short.
+    ZigZag,
+    /// Two's complement in big-endian, without redundant sign bytes. Zero is
+    /// the empty string.
+    TwosComplement,
+}
+
+/// Representation of `Option` values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OptionEncoding {
+    /// `None` is the empty string, `Some(x)` is `x`. This can't tell `None`
+    /// from `Some` of a value that encodes as the empty string, like 0 or "".
+    #[default]
+    EmptyString,
+    /// `None` is the empty list, `Some(x)` is `[x]`.
+    List,
+    /// Optional trailing fields, like `baseFeePerGas` in block headers. `None`
+    /// fields at the end of a struct are left out, and missing fields decode
+    /// as `None`. Anywhere else `None` is the empty string. Only `Option`
+    /// fields may be missing, others fail with a missing field error even if
+    /// they have `#[serde(default)]`.
+    Trailing,
+}
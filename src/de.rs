@@ -1,94 +1,607 @@
+use std::any::type_name;
 use std::collections::VecDeque;
-use std::ops::{AddAssign, MulAssign, Neg};
+use std::io::{self, Read};
+use std::marker::PhantomData;
 
-use serde::de::{self, Deserialize, DeserializeSeed, SeqAccess, Visitor};
+use num::FromPrimitive;
 
-use rlp::{self, ExpectedType};
+use serde::de::value::U32Deserializer;
+use serde::de::{
+    self, Deserialize, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use serde::forward_to_deserialize_any;
 
-use error::{Error, Result};
+use rlp::{self, DecodeLengthResult, ExpectedType};
+
+use config::{OptionEncoding, SignedEncoding, StructEncoding, VariantTag};
+use error::{Error, ErrorKind, NonCanonical, Result};
 use std::str;
 
+/// Default for `DecodeOptions::max_depth`. Ethereum data is only nested a few
+/// levels deep.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Default for `DecodeOptions::max_length`, the largest message devp2p accepts.
+pub const DEFAULT_MAX_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Default for `DecodeOptions::max_elements`.
+pub const DEFAULT_MAX_ELEMENTS: usize = 4 * 1024 * 1024;
+
+/// Settings of `Deserializer`, `StreamDeserializer`, `from_bytes_with` and
+/// `from_reader_with`.
+///
+/// ```
+/// use serde_rlp::de::{DecodeOptions, Deserializer};
+///
+/// let options = DecodeOptions::new().strict(false).max_depth(16);
+/// let deserializer = Deserializer::with_options(&[0x80], options);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Reject non-canonical integers and length prefixes
+    strict: bool,
+    /// Expected layout of structs
+    struct_encoding: StructEncoding,
+    /// Expected identifier of enum variants
+    variant_tag: VariantTag,
+    /// Expected representation of signed integers
+    signed_encoding: SignedEncoding,
+    /// Expected representation of optional values
+    option_encoding: OptionEncoding,
+    /// Deepest nesting of lists allowed
+    max_depth: usize,
+    /// Longest item allowed, in bytes
+    max_length: usize,
+    /// Most list elements allowed in total
+    max_elements: usize,
+}
+
+impl DecodeOptions {
+    pub fn new() -> Self {
+        DecodeOptions {
+            strict: true,
+            struct_encoding: StructEncoding::default(),
+            variant_tag: VariantTag::default(),
+            signed_encoding: SignedEncoding::default(),
+            option_encoding: OptionEncoding::default(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_length: DEFAULT_MAX_LENGTH,
+            max_elements: DEFAULT_MAX_ELEMENTS,
+        }
+    }
+
+    /// Enables or disables strict mode, which is on by default. In strict mode
+    /// integers with leading zero bytes and length prefixes that are not
+    /// minimally encoded are rejected, the same way Ethereum clients do.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets the expected layout of structs. Defaults to `StructEncoding::List`.
+    pub fn struct_encoding(mut self, encoding: StructEncoding) -> Self {
+        self.struct_encoding = encoding;
+        self
+    }
+
+    /// Sets how enum variants are identified. Defaults to `VariantTag::Name`.
+    pub fn variant_tag(mut self, tag: VariantTag) -> Self {
+        self.variant_tag = tag;
+        self
+    }
+
+    /// Sets how signed integers are represented. Defaults to
+    /// `SignedEncoding::Reject`.
+    pub fn signed_encoding(mut self, encoding: SignedEncoding) -> Self {
+        self.signed_encoding = encoding;
+        self
+    }
+
+    /// Sets how `Option` values are represented. Defaults to
+    /// `OptionEncoding::EmptyString`.
+    pub fn option_encoding(mut self, encoding: OptionEncoding) -> Self {
+        self.option_encoding = encoding;
+        self
+    }
+
+    /// Sets how deep lists can be nested, to bound recursion on untrusted
+    /// input. Defaults to `DEFAULT_MAX_DEPTH`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets the length of the longest item accepted, in bytes. Defaults to
+    /// `DEFAULT_MAX_LENGTH`. Readers reject longer items before reading their
+    /// payload.
+    pub fn max_length(mut self, length: usize) -> Self {
+        self.max_length = length;
+        self
+    }
+
+    /// Sets how many list elements can be decoded in total, to bound the
+    /// memory used by the decoded value. Defaults to `DEFAULT_MAX_ELEMENTS`.
+    /// Items of a stream are counted separately.
+    pub fn max_elements(mut self, elements: usize) -> Self {
+        self.max_elements = elements;
+        self
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions::new()
+    }
+}
+
 pub struct Deserializer<'de> {
     input: &'de [u8],
+    /// Whole input, to report positions of errors
+    origin: &'de [u8],
     /// Stacked input slices for nested data
     stack: VecDeque<&'de [u8]>,
+    /// Items entered on the way to the current one
+    path: Vec<Segment<'de>>,
+    options: DecodeOptions,
+    /// List elements decoded so far
+    elements: usize,
 }
 
 impl<'de> Deserializer<'de> {
+    /// Deserializer with the default settings. See `with_options` for the
+    /// position of errors.
     pub fn from_bytes(input: &'de [u8]) -> Self {
+        Deserializer::with_options(input, DecodeOptions::new())
+    }
+
+    /// Deserializer with the given settings.
+    ///
+    /// Errors returned while driving it directly, as in
+    /// `T::deserialize(&mut deserializer)`, carry no position: their `offset`
+    /// and `path` are `None`. The position is left in `byte_offset` and `path`
+    /// of the deserializer instead. `from_bytes_with` attaches it for you.
+    pub fn with_options(input: &'de [u8], options: DecodeOptions) -> Self {
         Deserializer {
-            input: input,
+            input,
+            origin: input,
             stack: VecDeque::new(),
+            path: Vec::new(),
+            options,
+            elements: 0,
+        }
+    }
+
+    /// Checks that the whole input was consumed.
+    pub fn end(&self) -> Result<()> {
+        if self.input.is_empty() {
+            Ok(())
+        } else {
+            Err(ErrorKind::TrailingBytes.into())
+        }
+    }
+
+    /// Offset of the next item in the input. After an error it points at the
+    /// item that failed.
+    pub fn byte_offset(&self) -> usize {
+        self.input.as_ptr() as usize - self.origin.as_ptr() as usize
+    }
+
+    /// Path of the current item, like `transactions[17].value`. After an error
+    /// it is the path of the item that failed.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in &self.path {
+            match *segment {
+                Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+                Segment::Field(name) => {
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(name);
+                }
+                Segment::Key(key) => match str::from_utf8(key) {
+                    Ok(key) => {
+                        if !path.is_empty() {
+                            path.push('.');
+                        }
+                        path.push_str(key);
+                    }
+                    Err(_) => {
+                        path.push_str("[0x");
+                        for byte in key {
+                            path.push_str(&format!("{:02x}", byte));
+                        }
+                        path.push(']');
+                    }
+                },
+            }
         }
+        path
+    }
+
+    /// Attaches the current position to an error raised while decoding.
+    fn locate(&self, error: Error) -> Error {
+        error.at(self.byte_offset(), self.path())
     }
 }
 
+/// Step on the path from the top level item to a nested one.
+enum Segment<'de> {
+    /// Position in a list
+    Index(usize),
+    /// Struct field
+    Field(&'static str),
+    /// Key of a map entry
+    Key(&'de [u8]),
+}
+
+/// Deserializes `T` from the whole input. Errors carry the offset and path of
+/// the item that failed, see `Error::offset` and `Error::path`.
 pub fn from_bytes<'a, T>(s: &'a [u8]) -> Result<T>
 where
     T: Deserialize<'a>,
 {
-    let mut deserializer = Deserializer::from_bytes(s);
-    let t = T::deserialize(&mut deserializer)?;
-    if deserializer.input.len() == 0 {
-        Ok(t)
-    } else {
-        Err(Error::TrailingBytes)
+    from_bytes_with(s, DecodeOptions::new())
+}
+
+/// Like `from_bytes`, with the given settings.
+pub fn from_bytes_with<'a, T>(s: &'a [u8], options: DecodeOptions) -> Result<T>
+where
+    T: Deserialize<'a>,
+{
+    let mut deserializer = Deserializer::with_options(s, options);
+    T::deserialize(&mut deserializer)
+        .and_then(|t| deserializer.end().map(|_| t))
+        .map_err(|e| deserializer.locate(e))
+}
+
+/// Deserializes a single RLP item read from `reader`.
+///
+/// Only the bytes announced by the item header are read, anything after the
+/// item is left in the reader. Input ending before the item is complete fails
+/// with `ErrorKind::UnexpectedEof`.
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    from_reader_with(reader, DecodeOptions::new())
+}
+
+/// Like `from_reader`, with the given settings. Items longer than
+/// `max_length` are rejected before their payload is read.
+pub fn from_reader_with<R, T>(mut reader: R, options: DecodeOptions) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut item = Vec::new();
+    match read_item(&mut reader, &mut item, options.max_length) {
+        Ok(true) => from_bytes_with(&item, options),
+        Ok(false) => Err(Error::from(ErrorKind::UnexpectedEof { missing: 1 }).at(0, String::new())),
+        Err(e) => Err(e.at(0, String::new())),
     }
 }
 
+/// Iterator over a concatenation of RLP items, such as a chain export file.
+///
+/// Each item is read from `reader` and deserialized into `T` on its own. A
+/// slice can be used as the reader. Iteration ends at the end of the input or
+/// after the first error.
+pub struct StreamDeserializer<R, T> {
+    reader: R,
+    /// Bytes of the current item
+    buf: Vec<u8>,
+    /// Offset of the next item in the input
+    offset: usize,
+    failed: bool,
+    options: DecodeOptions,
+    output: PhantomData<T>,
+}
+
+impl<R, T> StreamDeserializer<R, T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    pub fn new(reader: R) -> Self {
+        StreamDeserializer::with_options(reader, DecodeOptions::new())
+    }
+
+    pub fn with_options(reader: R, options: DecodeOptions) -> Self {
+        StreamDeserializer {
+            reader,
+            buf: Vec::new(),
+            offset: 0,
+            failed: false,
+            options,
+            output: PhantomData,
+        }
+    }
+
+    /// Offset in the input of the item returned by the next call to `next`.
+    /// After an error it is the offset of the item that failed.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    fn decode(&self) -> Result<T> {
+        from_bytes_with(&self.buf, self.options).map_err(|e| e.shifted(self.offset))
+    }
+}
+
+impl<R, T> Iterator for StreamDeserializer<R, T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        let res = match read_item(&mut self.reader, &mut self.buf, self.options.max_length) {
+            Ok(false) => return None,
+            Ok(true) => self.decode(),
+            // Reading errors are reported at the start of the item
+            Err(e) => Err(e.at(self.offset, String::new())),
+        };
+        match res {
+            Ok(_) => self.offset += self.buf.len(),
+            Err(_) => self.failed = true,
+        }
+        Some(res)
+    }
+}
+
+/// Reads the next complete item from `reader` into `buf`, replacing its
+/// contents. Returns `false` if the reader was already exhausted. Items longer
+/// than `max_length` are rejected without reading their payload.
+fn read_item<R: io::Read>(reader: &mut R, buf: &mut Vec<u8>, max_length: usize) -> Result<bool> {
+    buf.clear();
+    let mut prefix = [0u8];
+    loop {
+        match reader.read(&mut prefix) {
+            Ok(0) => return Ok(false),
+            Ok(_) => break,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    buf.push(prefix[0]);
+
+    let header_size = rlp::header_size(prefix[0]);
+    if header_size > 1 {
+        read_exactly(reader, buf, header_size - 1)?;
+    }
+    let header = rlp::decode_header(buf)?;
+    if header_size == 0 {
+        return Ok(true);
+    }
+    if header.length > max_length {
+        return Err(ErrorKind::LengthExceeded {
+            length: header.length,
+            limit: max_length,
+        }
+        .into());
+    }
+
+    // The payload is read through `take` rather than into a buffer of the
+    // announced size, so a bogus header cannot force a huge allocation.
+    read_exactly(reader, buf, header.length)?;
+    Ok(true)
+}
+
+/// Appends the next `length` bytes of `reader` to `buf`.
+fn read_exactly<R: io::Read>(reader: &mut R, buf: &mut Vec<u8>, length: usize) -> Result<()> {
+    let read = reader.take(length as u64).read_to_end(buf)?;
+    if read < length {
+        return Err(ErrorKind::UnexpectedEof {
+            missing: length - read,
+        }
+        .into());
+    }
+    Ok(())
+}
+
 impl<'de> Deserializer<'de> {
-    // Parse the JSON identifier `true` or `false`.
+    /// Decodes header of the next item, validating it in strict mode.
+    fn decode_length(&self) -> Result<DecodeLengthResult> {
+        let res = rlp::decode_length(self.input)?;
+        if self.options.strict {
+            rlp::check_canonical_length(self.input, &res)?;
+        }
+        if res.length > self.options.max_length {
+            return Err(ErrorKind::LengthExceeded {
+                length: res.length,
+                limit: self.options.max_length,
+            }
+            .into());
+        }
+        Ok(res)
+    }
+
+    /// Counts one more list element against `max_elements`.
+    fn count_element(&mut self) -> Result<()> {
+        if self.elements == self.options.max_elements {
+            return Err(ErrorKind::ElementsExceeded {
+                limit: self.options.max_elements,
+            }
+            .into());
+        }
+        self.elements += 1;
+        Ok(())
+    }
+
+    /// Decodes the next string with `f`. The string is consumed only if that
+    /// succeeds, so errors point at the start of the offending item.
+    fn parse_bytes_with<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&'de [u8]) -> Result<T>,
+    {
+        let start = self.input;
+        let res = self.parse_bytes().and_then(f);
+        if res.is_err() {
+            self.input = start;
+        }
+        res
+    }
+
     fn parse_bool(&mut self) -> Result<bool> {
-        unimplemented!();
+        self.parse_bytes_with(|bytes| match bytes {
+            [] => Ok(false),
+            [0x01] => Ok(true),
+            _ => Err(ErrorKind::InvalidBool.into()),
+        })
     }
 
     fn parse_unsigned<T>(&mut self) -> Result<T>
     where
-        T: AddAssign<T> + MulAssign<T> + From<u8>,
+        T: FromPrimitive,
     {
-        unimplemented!();
+        // Integers are RLP strings holding big-endian bytes. Anything that does
+        // not fit into the target type is rejected rather than truncated.
+        let strict = self.options.strict;
+        self.parse_bytes_with(|bytes| {
+            if strict && bytes.first() == Some(&0) {
+                return Err(ErrorKind::NonCanonical(NonCanonical::IntegerPadding).into());
+            }
+            rlp::decode_u128(bytes).ok().and_then(T::from_u128).ok_or(
+                ErrorKind::IntegerOverflow {
+                    target: type_name::<T>(),
+                }
+                .into(),
+            )
+        })
     }
 
     fn parse_signed<T>(&mut self) -> Result<T>
     where
-        T: Neg<Output = T> + AddAssign<T> + MulAssign<T> + From<i8>,
+        T: FromPrimitive,
     {
-        // Optional minus sign, delegate to `parse_unsigned`, negate if negative.
-        unimplemented!()
+        let strict = self.options.strict;
+        match self.options.signed_encoding {
+            SignedEncoding::Reject => Err(ErrorKind::UnsupportedType("signed integer").into()),
+            SignedEncoding::ZigZag => self.parse_bytes_with(|bytes| {
+                if strict && bytes.first() == Some(&0) {
+                    return Err(ErrorKind::NonCanonical(NonCanonical::IntegerPadding).into());
+                }
+                rlp::decode_u128(bytes)
+                    .ok()
+                    .and_then(|value| T::from_i128(rlp::zigzag_decode(value)))
+                    .ok_or(
+                        ErrorKind::IntegerOverflow {
+                            target: type_name::<T>(),
+                        }
+                        .into(),
+                    )
+            }),
+            SignedEncoding::TwosComplement => self.parse_bytes_with(|bytes| {
+                if strict && !rlp::is_canonical_twos_complement(bytes) {
+                    return Err(ErrorKind::NonCanonical(NonCanonical::IntegerPadding).into());
+                }
+                rlp::decode_twos_complement(bytes)
+                    .ok()
+                    .and_then(T::from_i128)
+                    .ok_or(
+                        ErrorKind::IntegerOverflow {
+                            target: type_name::<T>(),
+                        }
+                        .into(),
+                    )
+            }),
+        }
+    }
+
+    /// Like `parse_bytes_with`, for strings.
+    fn parse_string_with<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&'de str) -> Result<T>,
+    {
+        let start = self.input;
+        let res = self.parse_string().and_then(f);
+        if res.is_err() {
+            self.input = start;
+        }
+        res
     }
 
     fn parse_string(&mut self) -> Result<&'de str> {
-        let res = rlp::decode_length(&self.input)?;
+        let res = self.decode_length()?;
         if res.expected_type == ExpectedType::StringType {
             let s = str::from_utf8(&self.input[res.offset..res.offset + res.length])
-                .map_err(|_| Error::InvalidString)?;
+                .map_err(|_| ErrorKind::InvalidString)?;
             self.input = &self.input[res.offset + res.length..];
             Ok(s)
         } else {
-            Err(Error::ExpectedString)
+            Err(ErrorKind::ExpectedString.into())
+        }
+    }
+
+    /// Descends into the list at the front of the input. Items of that list
+    /// become the input until `leave_list` is called.
+    fn enter_list(&mut self) -> Result<()> {
+        if self.stack.len() == self.options.max_depth {
+            return Err(ErrorKind::DepthExceeded {
+                limit: self.options.max_depth,
+            }
+            .into());
         }
+        let res = self.decode_length()?;
+        if res.expected_type == ExpectedType::ListType {
+            let end = res.offset + res.length;
+            self.stack.push_front(&self.input[end..]);
+            self.input = &self.input[res.offset..end];
+            Ok(())
+        } else {
+            Err(ErrorKind::ExpectedList.into())
+        }
+    }
+
+    /// Goes back to the enclosing data, right past the current list.
+    fn leave_list(&mut self) -> Result<()> {
+        if !self.input.is_empty() {
+            // Visitor did not consume all elements of the list
+            return Err(ErrorKind::TrailingBytes.into());
+        }
+        self.input = self.stack.pop_front().unwrap(); // This unwrap is safe as long as calls are paired with `enter_list`.
+        Ok(())
+    }
+
+    /// Drops the next item, string or list, from the input.
+    fn skip_item(&mut self) -> Result<()> {
+        let res = self.decode_length()?;
+        self.input = &self.input[res.offset + res.length..];
+        Ok(())
     }
 
     fn parse_bytes(&mut self) -> Result<&'de [u8]> {
-        let res = rlp::decode_length(&self.input)?;
+        let res = self.decode_length()?;
         if res.expected_type == ExpectedType::StringType {
             let s = &self.input[res.offset..res.offset + res.length];
             self.input = &self.input[res.offset + res.length..];
             Ok(s)
         } else {
-            Err(Error::ExpectedString)
+            Err(ErrorKind::ExpectedString.into())
         }
     }
 }
 
-impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
-    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    // RLP only knows strings and lists, so self-describing data is handed to
+    // the visitor as bytes or as a sequence.
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        match self.decode_length()?.expected_type {
+            ExpectedType::StringType => self.deserialize_bytes(visitor),
+            ExpectedType::ListType => self.deserialize_seq(visitor),
+        }
     }
 
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
@@ -128,6 +641,13 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_i64(self.parse_signed()?)
     }
 
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_i128(self.parse_signed()?)
+    }
+
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
@@ -156,27 +676,43 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_u64(self.parse_unsigned()?)
     }
 
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_u128(self.parse_unsigned()?)
+    }
+
+    // RLP has no representation for floating point numbers.
     fn deserialize_f32<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("f32").into())
     }
 
-    // Float parsing is stupidly hard.
     fn deserialize_f64<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        Err(ErrorKind::UnsupportedType("f64").into())
     }
 
-    fn deserialize_char<V>(self, _visitor: V) -> Result<V::Value>
+    // Characters are serialized as one character strings.
+    fn deserialize_char<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        // Parse a string, check that it is one character, call `visit_char`.
-        unimplemented!()
+        self.parse_string_with(|s| {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => visitor.visit_char(c),
+                _ => Err(de::Error::invalid_value(
+                    de::Unexpected::Str(s),
+                    &"a single character",
+                )),
+            }
+        })
     }
 
     // Refer to the "Understanding deserializer lifetimes" page for information
@@ -185,7 +721,7 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.parse_string()?)
+        self.parse_string_with(|s| visitor.visit_borrowed_str(s))
     }
 
     fn deserialize_string<V>(self, visitor: V) -> Result<V::Value>
@@ -201,28 +737,48 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_bytes(self.parse_bytes()?)
+        self.parse_bytes_with(|bytes| visitor.visit_borrowed_bytes(bytes))
     }
 
-    fn deserialize_byte_buf<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!()
+        self.deserialize_bytes(visitor)
     }
 
-    fn deserialize_option<V>(self, _visitor: V) -> Result<V::Value>
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        match self.options.option_encoding {
+            OptionEncoding::EmptyString | OptionEncoding::Trailing => {
+                let res = self.decode_length()?;
+                if res.expected_type == ExpectedType::StringType && res.length == 0 {
+                    self.input = &self.input[res.offset..];
+                    visitor.visit_none()
+                } else {
+                    visitor.visit_some(self)
+                }
+            }
+            OptionEncoding::List => {
+                self.enter_list()?;
+                let value = if self.input.is_empty() {
+                    visitor.visit_none::<Error>()?
+                } else {
+                    visitor.visit_some(&mut *self)?
+                };
+                self.leave_list()?;
+                Ok(value)
+            }
+        }
     }
 
     fn deserialize_unit<V>(self, _visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        Err(ErrorKind::UnsupportedType("unit").into())
     }
 
     fn deserialize_unit_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
@@ -239,22 +795,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         visitor.visit_newtype_struct(self)
     }
 
-    fn deserialize_seq<V>(mut self, visitor: V) -> Result<V::Value>
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let res = rlp::decode_length(&self.input)?;
-        if res.expected_type == ExpectedType::ListType {
-            let nested = &self.input[res.offset..res.offset + res.length];
-            self.stack.push_front(&self.input);
-            self.input = nested;
-            let value = visitor.visit_seq(RlpListDecoder::new(&mut self))?;
-            self.input = self.stack.pop_front().unwrap();
-            self.input = &self.input[res.offset + res.length..];
-            Ok(value)
-        } else {
-            Err(Error::ExpectedList)
-        }
+        self.enter_list()?;
+        let value = visitor.visit_seq(RlpListDecoder::new(self))?;
+        self.leave_list()?;
+        Ok(value)
     }
 
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
@@ -276,35 +824,52 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_seq(visitor)
     }
 
-    fn deserialize_map<V>(self, _visitor: V) -> Result<V::Value>
+    // Maps are lists of `[key, value]` pairs.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        self.enter_list()?;
+        let value = visitor.visit_map(RlpListDecoder::new(self))?;
+        self.leave_list()?;
+        Ok(value)
     }
 
     fn deserialize_struct<V>(
         self,
         _name: &'static str,
-        _fields: &'static [&'static str],
+        fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_map(visitor)
+        match self.options.struct_encoding {
+            // Fields are visited in declaration order, same as a tuple
+            StructEncoding::List => {
+                self.enter_list()?;
+                let value = visitor.visit_seq(RlpListDecoder::for_struct(self, fields))?;
+                self.leave_list()?;
+                Ok(value)
+            }
+            StructEncoding::KeyValue => self.deserialize_map(visitor),
+        }
     }
 
     fn deserialize_enum<V>(
         self,
         _name: &'static str,
-        _variants: &'static [&'static str],
-        _visitor: V,
+        variants: &'static [&'static str],
+        visitor: V,
     ) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        unimplemented!();
+        // Variants are lists with the tag followed by the payload
+        self.enter_list()?;
+        let value = visitor.visit_enum(RlpListDecoder::for_enum(self, variants))?;
+        self.leave_list()?;
+        Ok(value)
     }
 
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value>
@@ -314,11 +879,14 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
         self.deserialize_str(visitor)
     }
 
+    // Ignored items are skipped as a whole using their header, nested items
+    // are neither visited nor allocated.
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        self.deserialize_any(visitor)
+        self.skip_item()?;
+        visitor.visit_unit()
     }
 }
 
@@ -327,11 +895,106 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
 // element.
 struct RlpListDecoder<'a, 'de: 'a> {
     de: &'a mut Deserializer<'de>,
+    /// Position of the next element
+    index: usize,
+    /// Names of the elements, for positional structs
+    fields: &'static [&'static str],
+    /// With `OptionEncoding::Trailing` all struct fields are visited, even
+    /// past the end of the list, to decode missing ones as `None`.
+    trailing: bool,
 }
 
 impl<'a, 'de> RlpListDecoder<'a, 'de> {
     fn new(de: &'a mut Deserializer<'de>) -> Self {
-        RlpListDecoder { de }
+        RlpListDecoder {
+            de,
+            index: 0,
+            fields: &[],
+            trailing: false,
+        }
+    }
+
+    /// Decoder for the fields of a positional struct.
+    fn for_struct(de: &'a mut Deserializer<'de>, fields: &'static [&'static str]) -> Self {
+        let trailing = de.options.option_encoding == OptionEncoding::Trailing;
+        RlpListDecoder {
+            de,
+            index: 0,
+            fields,
+            trailing,
+        }
+    }
+
+    /// Decoder for an enum variant. Until the tag is read, `fields` holds the
+    /// names of the variants.
+    fn for_enum(de: &'a mut Deserializer<'de>, variants: &'static [&'static str]) -> Self {
+        RlpListDecoder {
+            de,
+            index: 0,
+            fields: variants,
+            trailing: false,
+        }
+    }
+
+    /// Decodes the next element, keeping track of its path. The path is left
+    /// in place on errors, so they can be located.
+    fn element<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let segment = match self.fields.get(self.index) {
+            Some(name) => Segment::Field(name),
+            None => Segment::Index(self.index),
+        };
+        self.de.count_element()?;
+        self.de.path.push(segment);
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.path.pop();
+        self.index += 1;
+        Ok(value)
+    }
+
+    /// Decodes a struct field missing from the end of the list, which is only
+    /// allowed for options.
+    fn missing_field<T>(&mut self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let name = self.fields[self.index];
+        self.de.path.push(Segment::Field(name));
+        let value = seed.deserialize(MissingField(name))?;
+        self.de.path.pop();
+        self.index += 1;
+        Ok(value)
+    }
+}
+
+/// Stands in for a struct field that is missing from the end of the list. It
+/// decodes to `None`, any other type is reported as a missing field. That
+/// includes fields with `#[serde(default)]`, which can't be told apart here.
+struct MissingField(&'static str);
+
+impl<'de> de::Deserializer<'de> for MissingField {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        Err(de::Error::missing_field(self.0))
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_none()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
     }
 }
 
@@ -344,21 +1007,137 @@ impl<'de, 'a> SeqAccess<'de> for RlpListDecoder<'a, 'de> {
     where
         T: DeserializeSeed<'de>,
     {
-        if self.de.input.len() == 0 {
+        if self.de.input.is_empty() {
+            if self.trailing && self.index < self.fields.len() {
+                return self.missing_field(seed).map(Some);
+            }
             // No more elements
             return Ok(None);
         }
-        match rlp::decode_length(&self.de.input)?.expected_type {
-            ExpectedType::StringType => {
-                let result = seed.deserialize(&mut *self.de);
-                result.map(Some)
+        // Here we don't consume boundaries of a list, and let the deserializer create new deserializer for this sequence.
+        self.element(seed).map(Some)
+    }
+}
+
+// `MapAccess` walks through a list of `[key, value]` pairs. Each pair is a
+// nested list that is entered when reading the key, and left after the value.
+impl<'de, 'a> MapAccess<'de> for RlpListDecoder<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.de.input.is_empty() {
+            // No more entries
+            return Ok(None);
+        }
+        self.de.count_element()?;
+        self.de.enter_list()?;
+        let input = self.de.input;
+        let key = match self.de.decode_length() {
+            Ok(ref res) if res.expected_type == ExpectedType::StringType => {
+                Segment::Key(&input[res.offset..res.offset + res.length])
+            }
+            // Not a string, so there is no name to show
+            _ => Segment::Index(self.index),
+        };
+        self.de.path.push(key);
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.leave_list()?;
+        self.de.path.pop();
+        self.index += 1;
+        Ok(value)
+    }
+}
+
+// `EnumAccess` reads the variant tag at the head of the list, and
+// `VariantAccess` reads the payload from the rest of that list.
+impl<'de, 'a> EnumAccess<'de> for RlpListDecoder<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(mut self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let tag = self.de.input;
+        let (variant, name) = match self.de.options.variant_tag {
+            VariantTag::Name => {
+                let variant = seed.deserialize(&mut *self.de)?;
+                let name = rlp::decode_length(tag)
+                    .map(|res| &tag[res.offset..res.offset + res.length])
+                    .ok()
+                    .and_then(|name| self.fields.iter().find(|field| field.as_bytes() == name));
+                (variant, name.cloned())
             }
-            ExpectedType::ListType => {
-                // Here we don't consume boundaries of a list, and let the deserializer create new deserializer for this sequence.
-                let result = seed.deserialize(&mut *self.de);
-                result.map(Some)
+            VariantTag::Index => {
+                let index: u32 = self.de.parse_unsigned()?;
+                let variant = seed.deserialize(U32Deserializer::<Error>::new(index));
+                if variant.is_err() {
+                    // Point at the tag rather than past it
+                    self.de.input = tag;
+                }
+                let variant = variant?;
+                (variant, self.fields.get(index as usize).cloned())
             }
-        }
+        };
+        // The payload is found under the variant name, and its elements are
+        // numbered by their position in the list, after the tag.
+        self.de
+            .path
+            .push(name.map_or(Segment::Index(0), Segment::Field));
+        self.fields = &[];
+        self.index = 1;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for RlpListDecoder<'a, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        self.de.path.pop();
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let value = seed.deserialize(&mut *self.de)?;
+        self.de.path.pop();
+        Ok(value)
+    }
+
+    fn tuple_variant<V>(mut self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = visitor.visit_seq(&mut self)?;
+        self.de.path.pop();
+        Ok(value)
+    }
+
+    fn struct_variant<V>(mut self, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let value = match self.de.options.struct_encoding {
+            StructEncoding::List => {
+                visitor.visit_seq(RlpListDecoder::for_struct(&mut *self.de, fields))?
+            }
+            StructEncoding::KeyValue => visitor.visit_map(&mut self)?,
+        };
+        self.de.path.pop();
+        Ok(value)
     }
 }
 
@@ -416,24 +1195,190 @@ fn deserialize_set_representation_of_three() {
 fn deserialize_three_levels() {
     let foo: Vec<Vec<Vec<String>>> = from_bytes(&[
         0xca, 0xc9, 0xc8, 0x83, 0x61, 0x62, 0x63, 0x83, 0x64, 0x65, 0x66,
-    ]).unwrap();
+    ])
+    .unwrap();
     assert_eq!(foo, [[["abc", "def"]]]);
 }
 
+#[test]
+fn deserialize_unsigned_integers() {
+    assert_eq!(from_bytes::<u8>(&[0x0f]).unwrap(), 15u8);
+    assert_eq!(from_bytes::<u8>(&[0x81, 0xff]).unwrap(), 255u8);
+    assert_eq!(from_bytes::<u16>(&[0x82, 0x04, 0x00]).unwrap(), 1024u16);
+    assert_eq!(from_bytes::<u32>(&[0x82, 0x04, 0x00]).unwrap(), 1024u32);
+    assert_eq!(from_bytes::<u64>(&[0x80]).unwrap(), 0u64);
+    assert_eq!(
+        from_bytes::<u64>(&[0x88, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap(),
+        u64::MAX
+    );
+}
+
+#[test]
+fn deserialize_unsigned_overflow() {
+    assert_eq!(
+        from_bytes::<u8>(&[0x82, 0x01, 0x00])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::IntegerOverflow { target: "u8" }
+    );
+    assert_eq!(
+        from_bytes::<u32>(&[0x85, 0x01, 0x00, 0x00, 0x00, 0x00])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::IntegerOverflow { target: "u32" }
+    );
+    assert_eq!(
+        from_bytes::<u64>(&[0x89, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::IntegerOverflow { target: "u64" }
+    );
+}
+
+#[test]
+fn deserialize_integer_roundtrip() {
+    use ser::to_bytes;
+    for &v in &[0u64, 1, 0x7f, 0x80, 0xff, 1024, 0xffff_ffff, u64::MAX] {
+        assert_eq!(from_bytes::<u64>(&to_bytes(&v).unwrap()).unwrap(), v);
+    }
+    // Nonce and gas price of a transaction
+    let data = (9u64, 20_000_000_000u64);
+    assert_eq!(
+        from_bytes::<(u64, u64)>(&to_bytes(&data).unwrap()).unwrap(),
+        data
+    );
+}
+
+#[test]
+fn strict_rejects_leading_zeros() {
+    assert_eq!(
+        from_bytes::<u64>(&[0x82, 0x00, 0x01])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::IntegerPadding)
+    );
+    assert_eq!(
+        from_bytes::<u8>(&[0x00]).unwrap_err().into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::IntegerPadding)
+    );
+}
+
+#[test]
+fn strict_rejects_non_minimal_prefixes() {
+    assert_eq!(
+        from_bytes::<u8>(&[0x81, 0x05]).unwrap_err().into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::SingleByteString)
+    );
+    assert_eq!(
+        from_bytes::<String>(&[0xb8, 0x03, 0x61, 0x62, 0x63])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::LongForm)
+    );
+    assert_eq!(
+        from_bytes::<Vec<String>>(&[0xf8, 0x04, 0x83, 0x61, 0x62, 0x63])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::LongForm)
+    );
+    // Non-minimal item nested in a canonical list
+    assert_eq!(
+        from_bytes::<Vec<u8>>(&[0xc2, 0x81, 0x05])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::SingleByteString)
+    );
+}
+
+#[test]
+fn lenient_accepts_non_canonical() {
+    let mut de =
+        Deserializer::with_options(&[0x82, 0x00, 0x01], DecodeOptions::new().strict(false));
+    assert_eq!(u64::deserialize(&mut de).unwrap(), 1);
+    assert!(de.end().is_ok());
+
+    let mut de = Deserializer::with_options(&[0x00], DecodeOptions::new().strict(false));
+    assert_eq!(u8::deserialize(&mut de).unwrap(), 0);
+
+    let mut de = Deserializer::with_options(
+        &[0xb8, 0x03, 0x61, 0x62, 0x63],
+        DecodeOptions::new().strict(false),
+    );
+    assert_eq!(String::deserialize(&mut de).unwrap(), "abc");
+
+    let mut de = Deserializer::with_options(
+        &[0xf8, 0x04, 0x83, 0x61, 0x62, 0x63],
+        DecodeOptions::new().strict(false),
+    );
+    assert_eq!(Vec::<String>::deserialize(&mut de).unwrap(), vec!["abc"]);
+}
+
+#[test]
+fn deserialize_tuple_with_extra_elements() {
+    assert_eq!(
+        from_bytes::<(String,)>(&[0xc8, 0x83, 0x61, 0x62, 0x63, 0x83, 0x64, 0x65, 0x66])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::TrailingBytes
+    );
+}
+
+#[test]
+fn deserialize_map_of_pairs() {
+    use std::collections::BTreeMap;
+    // [["key1", "value1"], ["key2", "value2"]]
+    let data = [
+        0xda, 0xcc, 0x84, 0x6b, 0x65, 0x79, 0x31, 0x86, 0x76, 0x61, 0x6c, 0x75, 0x65, 0x31, 0xcc,
+        0x84, 0x6b, 0x65, 0x79, 0x32, 0x86, 0x76, 0x61, 0x6c, 0x75, 0x65, 0x32,
+    ];
+    let map: BTreeMap<String, String> = from_bytes(&data).unwrap();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map["key1"], "value1");
+    assert_eq!(map["key2"], "value2");
+}
+
+#[test]
+fn deserialize_map_with_malformed_pair() {
+    use std::collections::BTreeMap;
+    // [["key1", "value1", "extra"]]
+    let data = [
+        0xd3, 0xd2, 0x84, 0x6b, 0x65, 0x79, 0x31, 0x86, 0x76, 0x61, 0x6c, 0x75, 0x65, 0x31, 0x85,
+        0x65, 0x78, 0x74, 0x72, 0x61,
+    ];
+    assert_eq!(
+        from_bytes::<BTreeMap<String, String>>(&data)
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::TrailingBytes
+    );
+    // ["key1", "value1"]
+    let data = [
+        0xcc, 0x84, 0x6b, 0x65, 0x79, 0x31, 0x86, 0x76, 0x61, 0x6c, 0x75, 0x65, 0x31,
+    ];
+    assert_eq!(
+        from_bytes::<BTreeMap<String, String>>(&data)
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::ExpectedList
+    );
+}
+
 #[test]
 #[should_panic]
 fn simple_invalid() {
     let _foo: String = from_bytes(&[0x83, 0x61, 0x62, 0x63, /* excess */ 0xff]).unwrap();
 }
 
+#[cfg(test)]
 fn get_bytes(b: &str) -> Option<Vec<u8>> {
     b.as_bytes()
         .chunks(2)
         .map(|ch| {
-            str::from_utf8(&ch)
+            str::from_utf8(ch)
                 .ok()
-                .and_then(|res| u8::from_str_radix(&res, 16).ok())
-        }).collect()
+                .and_then(|res| u8::from_str_radix(res, 16).ok())
+        })
+        .collect()
 }
 
 #[test]
@@ -442,8 +1387,8 @@ fn invalid_complex() {
     let data = get_bytes("f86103018207d094b94f5374fce5edbc8e2a8697c15331677e6ebf0b0a8255441ca098ff921201554726367d2be8c00804a7ff89ccf285ebc57dff8ae4c44b9c19ac4aa08887321be575c8095f789dd4c743dfe42c1820f9231f98a962b210e3ac2452a3").unwrap();
 
     assert_eq!(
-        from_bytes::<Vec<Bytes>>(&data).unwrap_err(),
-        Error::WrongPrefix
+        from_bytes::<Vec<Bytes>>(&data).unwrap_err().into_kind(),
+        ErrorKind::StringPrefixTooSmall
     );
 }
 
@@ -454,7 +1399,8 @@ fn lorem_ipsum() {
         0x6f, 0x6c, 0x6f, 0x72, 0x20, 0x73, 0x69, 0x74, 0x20, 0x61, 0x6d, 0x65, 0x74, 0x2c, 0x20,
         0x63, 0x6f, 0x6e, 0x73, 0x65, 0x63, 0x74, 0x65, 0x74, 0x75, 0x72, 0x20, 0x61, 0x64, 0x69,
         0x70, 0x69, 0x73, 0x69, 0x63, 0x69, 0x6e, 0x67, 0x20, 0x65, 0x6c, 0x69, 0x74,
-    ]).unwrap();
+    ])
+    .unwrap();
     assert_eq!(
         data,
         "Lorem ipsum dolor sit amet, consectetur adipisicing elit"
@@ -486,3 +1432,684 @@ fn unsigned_eth_transaction() {
         ]
     );
 }
+
+#[test]
+fn from_reader_reads_one_item() {
+    let data = [
+        0xc8, 0x83, b'c', b'a', b't', 0x83, b'd', b'o', b'g', 0x2a, 0x82, 0x04, 0x00,
+    ];
+    let mut reader = io::Cursor::new(&data[..]);
+    let animals: Vec<String> = from_reader(&mut reader).unwrap();
+    assert_eq!(animals, vec!["cat", "dog"]);
+    assert_eq!(reader.position(), 9);
+    let small: u64 = from_reader(&mut reader).unwrap();
+    assert_eq!(small, 42);
+    let big: u64 = from_reader(&mut reader).unwrap();
+    assert_eq!(big, 1024);
+    assert_eq!(
+        from_reader::<_, u64>(&mut reader).unwrap_err().into_kind(),
+        ErrorKind::UnexpectedEof { missing: 1 }
+    );
+}
+
+#[test]
+fn from_reader_long_list() {
+    let items = vec![String::from("Lorem ipsum dolor sit amet"); 10];
+    let data = ::ser::to_bytes(&items).unwrap();
+    let decoded: Vec<String> = from_reader(&data[..]).unwrap();
+    assert_eq!(decoded, items);
+}
+
+#[test]
+fn from_reader_with_options() {
+    let data = [0x82, 0x00, 0x01];
+    assert_eq!(
+        from_reader::<_, u16>(&data[..]).unwrap_err().into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::IntegerPadding)
+    );
+    let options = DecodeOptions::new().strict(false);
+    assert_eq!(from_reader_with::<_, u16>(&data[..], options).unwrap(), 1);
+
+    // The length limit is checked before the payload is read
+    let options = DecodeOptions::new().max_length(1);
+    assert_eq!(
+        from_reader_with::<_, u16>(&data[..], options)
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::LengthExceeded {
+            length: 2,
+            limit: 1
+        }
+    );
+}
+
+#[test]
+fn from_reader_truncated() {
+    // Header cut short
+    let res: Result<String> = from_reader(&[0xb8][..]);
+    assert_eq!(
+        res.unwrap_err().into_kind(),
+        ErrorKind::UnexpectedEof { missing: 1 }
+    );
+    // Payload cut short
+    let res: Result<String> = from_reader(&[0x83, b'c', b'a'][..]);
+    assert_eq!(
+        res.unwrap_err().into_kind(),
+        ErrorKind::UnexpectedEof { missing: 1 }
+    );
+    let res: Result<Vec<String>> = from_reader(&[0xf8, 0x40, 0x80][..]);
+    assert_eq!(
+        res.unwrap_err().into_kind(),
+        ErrorKind::UnexpectedEof { missing: 63 }
+    );
+}
+
+#[test]
+fn stream_of_items() {
+    let data = [
+        0xc4, 0x83, b'c', b'a', b't', 0xc0, 0xc7, 0x83, b'd', b'o', b'g', 0x82, b'o', b'x',
+    ];
+    let mut stream = StreamDeserializer::<_, Vec<String>>::new(&data[..]);
+    assert_eq!(stream.byte_offset(), 0);
+    assert_eq!(stream.next().unwrap().unwrap(), vec!["cat"]);
+    assert_eq!(stream.byte_offset(), 5);
+    assert_eq!(stream.next().unwrap().unwrap(), Vec::<String>::new());
+    assert_eq!(stream.byte_offset(), 6);
+    assert_eq!(stream.next().unwrap().unwrap(), vec!["dog", "ox"]);
+    assert_eq!(stream.byte_offset(), data.len());
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn stream_stops_after_error() {
+    // The second item is not a list, the third one is cut short
+    let data = [0xc1, 0x01, 0x02, 0xc1];
+    let mut stream = StreamDeserializer::<_, Vec<u8>>::new(&data[..]);
+    assert_eq!(stream.next().unwrap().unwrap(), vec![1]);
+    assert_eq!(
+        stream.next().unwrap().unwrap_err().into_kind(),
+        ErrorKind::ExpectedList
+    );
+    assert_eq!(stream.byte_offset(), 2);
+    assert!(stream.next().is_none());
+
+    let mut stream = StreamDeserializer::<_, Vec<u8>>::new(&data[3..]);
+    assert_eq!(
+        stream.next().unwrap().unwrap_err().into_kind(),
+        ErrorKind::UnexpectedEof { missing: 1 }
+    );
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn stream_of_nothing() {
+    let mut stream = StreamDeserializer::<_, u64>::new(io::empty());
+    assert!(stream.next().is_none());
+}
+
+#[test]
+fn deserialize_any_item() {
+    use value::RlpValue;
+    let data = [0xc6, 0x83, b'c', b'a', b't', 0xc1, 0x01];
+    let value: RlpValue = from_bytes(&data).unwrap();
+    assert_eq!(
+        value,
+        RlpValue::List(vec![
+            RlpValue::from("cat"),
+            RlpValue::List(vec![RlpValue::from(vec![1])]),
+        ])
+    );
+    let value: RlpValue = from_bytes(&[0x80]).unwrap();
+    assert_eq!(value, RlpValue::Bytes(vec![]));
+}
+
+#[test]
+fn ignore_items() {
+    use serde::de::IgnoredAny;
+    // [1, [[], "cat"], 2]
+    let data = [0xc8, 0x01, 0xc5, 0xc0, 0x83, b'c', b'a', b't', 0x02];
+    let (a, _, b): (u8, IgnoredAny, u8) = from_bytes(&data).unwrap();
+    assert_eq!((a, b), (1, 2));
+    from_bytes::<IgnoredAny>(&data).unwrap();
+    from_bytes::<IgnoredAny>(&[0x2a]).unwrap();
+}
+
+#[test]
+fn deserialize_char() {
+    assert_eq!(from_bytes::<char>(&[0x61]).unwrap(), 'a');
+    assert_eq!(from_bytes::<char>(&[0x82, 0xc5, 0x82]).unwrap(), '\u{142}');
+    assert!(from_bytes::<char>(&[0x80]).is_err());
+    assert!(from_bytes::<char>(&[0x82, 0x61, 0x62]).is_err());
+}
+
+#[test]
+fn deserialize_unsupported_types() {
+    assert_eq!(
+        from_bytes::<i32>(&[0x01]).unwrap_err().into_kind(),
+        ErrorKind::UnsupportedType("signed integer")
+    );
+    assert_eq!(
+        from_bytes::<i128>(&[0x01]).unwrap_err().into_kind(),
+        ErrorKind::UnsupportedType("signed integer")
+    );
+    assert_eq!(
+        from_bytes::<f32>(&[0x01]).unwrap_err().into_kind(),
+        ErrorKind::UnsupportedType("f32")
+    );
+    assert_eq!(
+        from_bytes::<f64>(&[0x01]).unwrap_err().into_kind(),
+        ErrorKind::UnsupportedType("f64")
+    );
+    assert_eq!(
+        from_bytes::<()>(&[0xc0]).unwrap_err().into_kind(),
+        ErrorKind::UnsupportedType("unit")
+    );
+}
+
+#[test]
+fn deserialize_signed_integers() {
+    let zigzag = |data| {
+        Deserializer::with_options(
+            data,
+            DecodeOptions::new().signed_encoding(SignedEncoding::ZigZag),
+        )
+    };
+    assert_eq!(i8::deserialize(&mut zigzag(&[0x80])).unwrap(), 0);
+    assert_eq!(i16::deserialize(&mut zigzag(&[0x01])).unwrap(), -1);
+    assert_eq!(i32::deserialize(&mut zigzag(&[0x81, 0x81])).unwrap(), -65);
+    assert_eq!(
+        i64::deserialize(&mut zigzag(&[0x82, 0x07, 0xd0])).unwrap(),
+        1000
+    );
+    assert_eq!(
+        i8::deserialize(&mut zigzag(&[0x82, 0x07, 0xd0]))
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::IntegerOverflow { target: "i8" }
+    );
+
+    let twos = |data| {
+        Deserializer::with_options(
+            data,
+            DecodeOptions::new().signed_encoding(SignedEncoding::TwosComplement),
+        )
+    };
+    assert_eq!(i8::deserialize(&mut twos(&[0x80])).unwrap(), 0);
+    assert_eq!(i16::deserialize(&mut twos(&[0x81, 0xff])).unwrap(), -1);
+    assert_eq!(i32::deserialize(&mut twos(&[0x7f])).unwrap(), 127);
+    assert_eq!(
+        i64::deserialize(&mut twos(&[0x82, 0x00, 0x80])).unwrap(),
+        128
+    );
+    assert_eq!(
+        i8::deserialize(&mut twos(&[0x82, 0x00, 0x80]))
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::IntegerOverflow { target: "i8" }
+    );
+    assert_eq!(
+        i16::deserialize(&mut twos(&[0x82, 0xff, 0xff]))
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::IntegerPadding)
+    );
+    assert_eq!(
+        i16::deserialize(&mut Deserializer::with_options(
+            &[0x82, 0xff, 0xff],
+            DecodeOptions::new()
+                .signed_encoding(SignedEncoding::TwosComplement)
+                .strict(false)
+        ))
+        .unwrap(),
+        -1
+    );
+}
+
+#[test]
+fn signed_integers_roundtrip() {
+    use ser::Serializer;
+    let values = [0, 1, -1, 127, -128, 128, -129, i64::MAX, i64::MIN];
+    for &encoding in &[SignedEncoding::ZigZag, SignedEncoding::TwosComplement] {
+        for &v in &values {
+            let data = Serializer::new()
+                .signed_encoding(encoding)
+                .to_bytes(&v)
+                .unwrap();
+            let mut deserializer =
+                Deserializer::with_options(&data, DecodeOptions::new().signed_encoding(encoding));
+            assert_eq!(i64::deserialize(&mut deserializer).unwrap(), v);
+        }
+        for &v in &[i128::MAX, i128::MIN, -1] {
+            let data = Serializer::new()
+                .signed_encoding(encoding)
+                .to_bytes(&v)
+                .unwrap();
+            let mut deserializer =
+                Deserializer::with_options(&data, DecodeOptions::new().signed_encoding(encoding));
+            assert_eq!(i128::deserialize(&mut deserializer).unwrap(), v);
+            // Too wide for an i64
+            if v != -1 {
+                let mut deserializer = Deserializer::with_options(
+                    &data,
+                    DecodeOptions::new().signed_encoding(encoding),
+                );
+                assert_eq!(
+                    i64::deserialize(&mut deserializer).unwrap_err().into_kind(),
+                    ErrorKind::IntegerOverflow { target: "i64" }
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn deserialize_u128() {
+    let mut data = vec![0x90];
+    data.extend_from_slice(&[0xff; 16]);
+    assert_eq!(from_bytes::<u128>(&data).unwrap(), u128::MAX);
+    assert_eq!(
+        from_bytes::<u64>(&data).unwrap_err().into_kind(),
+        ErrorKind::IntegerOverflow { target: "u64" }
+    );
+    data[0] = 0x91;
+    data.push(0xff);
+    assert_eq!(
+        from_bytes::<u128>(&data).unwrap_err().into_kind(),
+        ErrorKind::IntegerOverflow { target: "u128" }
+    );
+}
+
+#[test]
+fn deserialize_bool() {
+    assert!(from_bytes::<bool>(&[0x01]).unwrap());
+    assert!(!from_bytes::<bool>(&[0x80]).unwrap());
+    assert_eq!(
+        from_bytes::<(bool, bool)>(&[0xc2, 0x01, 0x80]).unwrap(),
+        (true, false)
+    );
+    for data in &[&[0x00][..], &[0x02], &[0x82, 0x00, 0x01]] {
+        assert_eq!(
+            from_bytes::<bool>(data).unwrap_err().into_kind(),
+            ErrorKind::InvalidBool
+        );
+    }
+    assert_eq!(
+        from_bytes::<bool>(&[0xc0]).unwrap_err().into_kind(),
+        ErrorKind::ExpectedString
+    );
+}
+
+#[test]
+fn deserialize_options() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Header {
+        number: u64,
+        base_fee: Option<u64>,
+        withdrawals: Option<u64>,
+    }
+    let header = |base_fee, withdrawals| Header {
+        number: 1,
+        base_fee,
+        withdrawals,
+    };
+
+    assert_eq!(from_bytes::<Option<u64>>(&[0x80]).unwrap(), None);
+    assert_eq!(from_bytes::<Option<u64>>(&[0x0f]).unwrap(), Some(15));
+    assert_eq!(
+        from_bytes::<Header>(&[0xc3, 0x01, 0x80, 0x07]).unwrap(),
+        header(None, Some(7))
+    );
+    assert_eq!(
+        from_bytes::<Header>(&[0xc2, 0x01, 0x07])
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::LengthMismatch {
+            expected: "struct Header with 3 elements".to_string(),
+            found: 2
+        }
+    );
+
+    let list = |data| {
+        Deserializer::with_options(
+            data,
+            DecodeOptions::new().option_encoding(OptionEncoding::List),
+        )
+    };
+    assert_eq!(
+        Option::<u64>::deserialize(&mut list(&[0xc0])).unwrap(),
+        None
+    );
+    assert_eq!(
+        Option::<u64>::deserialize(&mut list(&[0xc1, 0x80])).unwrap(),
+        Some(0)
+    );
+    assert_eq!(
+        Header::deserialize(&mut list(&[0xc4, 0x01, 0xc0, 0xc1, 0x07])).unwrap(),
+        header(None, Some(7))
+    );
+    assert_eq!(
+        Option::<u64>::deserialize(&mut list(&[0xc2, 0x01, 0x02]))
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::TrailingBytes
+    );
+
+    let trailing = |data| {
+        Deserializer::with_options(
+            data,
+            DecodeOptions::new().option_encoding(OptionEncoding::Trailing),
+        )
+    };
+    assert_eq!(
+        Header::deserialize(&mut trailing(&[0xc1, 0x01])).unwrap(),
+        header(None, None)
+    );
+    assert_eq!(
+        Header::deserialize(&mut trailing(&[0xc2, 0x01, 0x07])).unwrap(),
+        header(Some(7), None)
+    );
+    assert_eq!(
+        Header::deserialize(&mut trailing(&[0xc3, 0x01, 0x80, 0x07])).unwrap(),
+        header(None, Some(7))
+    );
+    // Required fields are still required
+    let mut deserializer = trailing(&[0xc0]);
+    let err = Header::deserialize(&mut deserializer).unwrap_err();
+    assert_eq!(
+        err.into_kind(),
+        ErrorKind::Message("missing field `number`".to_string())
+    );
+    assert_eq!(deserializer.path(), "number");
+
+    // Only options can be left out, defaults are not filled in
+    #[derive(Debug, Deserialize)]
+    struct Receipt {
+        _status: u8,
+        #[serde(default)]
+        _gas_used: u64,
+    }
+    assert_eq!(
+        Receipt::deserialize(&mut trailing(&[0xc1, 0x01]))
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::Message("missing field `_gas_used`".to_string())
+    );
+    assert!(from_bytes::<Receipt>(&[0xc1, 0x01]).is_ok());
+    // Only fields of a list can be missing
+    assert_eq!(
+        Option::<u64>::deserialize(&mut trailing(&[]))
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::EmptyBuffer
+    );
+}
+
+#[test]
+fn options_roundtrip() {
+    use ser::Serializer;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Message {
+        Ping { id: u64, payload: Option<String> },
+    }
+    let values = vec![
+        (
+            Some(1u64),
+            vec![
+                None,
+                Some(Message::Ping {
+                    id: 2,
+                    payload: None,
+                }),
+            ],
+        ),
+        (
+            None,
+            vec![Some(Message::Ping {
+                id: 3,
+                payload: Some("cat".to_string()),
+            })],
+        ),
+    ];
+    for &encoding in &[
+        OptionEncoding::EmptyString,
+        OptionEncoding::List,
+        OptionEncoding::Trailing,
+    ] {
+        for value in &values {
+            let data = Serializer::new()
+                .option_encoding(encoding)
+                .to_bytes(value)
+                .unwrap();
+            let mut deserializer =
+                Deserializer::with_options(&data, DecodeOptions::new().option_encoding(encoding));
+            let back: (Option<u64>, Vec<Option<Message>>) =
+                Deserialize::deserialize(&mut deserializer).unwrap();
+            assert_eq!(&back, value);
+        }
+    }
+}
+
+#[test]
+fn deserialize_byte_buf() {
+    use serde_bytes::ByteBuf;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Code(#[serde(with = "::serde_bytes")] Vec<u8>);
+
+    assert_eq!(
+        from_bytes::<ByteBuf>(&[0x82, 0x04, 0x00]).unwrap(),
+        ByteBuf::from(vec![0x04, 0x00])
+    );
+    assert_eq!(from_bytes::<ByteBuf>(&[0x80]).unwrap(), ByteBuf::new());
+    assert_eq!(
+        from_bytes::<Code>(&[0x83, 0x60, 0x00, 0xf3]).unwrap(),
+        Code(vec![0x60, 0x00, 0xf3])
+    );
+    assert_eq!(
+        from_bytes::<ByteBuf>(&[0xc0]).unwrap_err().into_kind(),
+        ErrorKind::ExpectedString
+    );
+}
+
+#[test]
+fn errors_are_located() {
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Transaction {
+        nonce: u64,
+        value: u8,
+    }
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Block {
+        number: u64,
+        transactions: Vec<Transaction>,
+    }
+
+    // [1, [[1, 2], [3, 4]]]
+    let data = [0xc8, 0x01, 0xc6, 0xc2, 0x01, 0x02, 0xc2, 0x03, 0x04];
+    let transaction = |nonce, value| Transaction { nonce, value };
+    assert_eq!(
+        from_bytes::<Block>(&data).unwrap(),
+        Block {
+            number: 1,
+            transactions: vec![transaction(1, 2), transaction(3, 4)],
+        }
+    );
+
+    // [1, [[1, 2], [3, 256]]]
+    let data = [
+        0xca, 0x01, 0xc8, 0xc2, 0x01, 0x02, 0xc4, 0x03, 0x82, 0x01, 0x00,
+    ];
+    let err = from_bytes::<Block>(&data).unwrap_err();
+    assert_eq!(err.offset(), Some(8));
+    assert_eq!(err.path(), Some("transactions[1].value"));
+    assert_eq!(
+        err.to_string(),
+        "Integer is too big for u8 at offset 8 in transactions[1].value"
+    );
+    assert_eq!(err.into_kind(), ErrorKind::IntegerOverflow { target: "u8" });
+
+    let err = from_bytes::<u8>(&[0x01, 0x02]).unwrap_err();
+    assert_eq!(err.offset(), Some(1));
+    assert_eq!(err.path(), Some(""));
+    assert_eq!(
+        err.to_string(),
+        "Trailing bytes found at the end of input at offset 1"
+    );
+
+    // [["a", 256]]
+    let data = [0xc5, 0xc4, 0x61, 0x82, 0x01, 0x00];
+    let err = from_bytes::<BTreeMap<String, u8>>(&data).unwrap_err();
+    assert_eq!(err.offset(), Some(3));
+    assert_eq!(err.path(), Some("a"));
+
+    let mut stream = StreamDeserializer::<_, u8>::new(&[0x01, 0xc1, 0xc0][..]);
+    assert_eq!(stream.next().unwrap().unwrap(), 1);
+    let err = stream.next().unwrap().unwrap_err();
+    assert_eq!(err.offset(), Some(1));
+    assert_eq!(err.into_kind(), ErrorKind::ExpectedString);
+}
+
+#[test]
+fn errors_of_a_driven_deserializer() {
+    // [1, 256]
+    let data = [0xc4, 0x01, 0x82, 0x01, 0x00];
+    let mut deserializer = Deserializer::from_bytes(&data);
+    let err = <(u8, u8)>::deserialize(&mut deserializer).unwrap_err();
+    assert_eq!(err.offset(), None);
+    assert_eq!(err.path(), None);
+    assert_eq!(deserializer.byte_offset(), 2);
+    assert_eq!(deserializer.path(), "[1]");
+}
+
+#[test]
+fn errors_in_variants_are_located() {
+    #[derive(Debug, PartialEq, Deserialize)]
+    enum Message {
+        Ping,
+        Transfer(u8, u8),
+        Deposit { amount: u8 },
+    }
+
+    // [["Ping"], ["Transfer", 1, 256]]
+    let mut data = vec![0xd4, 0xc5, 0x84];
+    data.extend_from_slice(b"Ping");
+    data.extend_from_slice(&[0xcd, 0x88]);
+    data.extend_from_slice(b"Transfer");
+    data.extend_from_slice(&[0x01, 0x82, 0x01, 0x00]);
+    let err = from_bytes::<Vec<Message>>(&data).unwrap_err();
+    assert_eq!(err.offset(), Some(18));
+    // Elements of a tuple variant are numbered after the tag
+    assert_eq!(err.path(), Some("[1].Transfer[2]"));
+
+    let by_index = DecodeOptions::new().variant_tag(VariantTag::Index);
+    // [1, 1, 256]
+    let err =
+        from_bytes_with::<Message>(&[0xc5, 0x01, 0x01, 0x82, 0x01, 0x00], by_index).unwrap_err();
+    assert_eq!(err.offset(), Some(3));
+    assert_eq!(err.path(), Some("Transfer[2]"));
+    // [2, 256]
+    let err = from_bytes_with::<Message>(&[0xc4, 0x02, 0x82, 0x01, 0x00], by_index).unwrap_err();
+    assert_eq!(err.offset(), Some(2));
+    assert_eq!(err.path(), Some("Deposit.amount"));
+}
+
+#[test]
+fn nesting_depth_is_limited() {
+    use serde::de::IgnoredAny;
+    use value::RlpValue;
+
+    // Lists nested a thousand levels deep
+    let mut data = vec![0xc0];
+    for _ in 0..1000 {
+        let mut outer = rlp::encode_length(data.len() as u64, 0xc0).to_vec();
+        outer.extend_from_slice(&data);
+        data = outer;
+    }
+    assert_eq!(
+        from_bytes::<RlpValue>(&data).unwrap_err().into_kind(),
+        ErrorKind::DepthExceeded {
+            limit: DEFAULT_MAX_DEPTH
+        }
+    );
+    // Skipped items are not descended into
+    assert!(from_bytes::<IgnoredAny>(&data).is_ok());
+
+    let data = [0xc2, 0xc1, 0xc0];
+    let mut deserializer = Deserializer::with_options(&data, DecodeOptions::new().max_depth(2));
+    assert_eq!(
+        RlpValue::deserialize(&mut deserializer)
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::DepthExceeded { limit: 2 }
+    );
+    let mut deserializer = Deserializer::with_options(&data, DecodeOptions::new().max_depth(3));
+    assert!(RlpValue::deserialize(&mut deserializer).is_ok());
+}
+
+#[test]
+fn item_length_is_limited() {
+    let mut deserializer = Deserializer::with_options(
+        &[0x83, 0x61, 0x62, 0x63],
+        DecodeOptions::new().max_length(2),
+    );
+    assert_eq!(
+        String::deserialize(&mut deserializer)
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::LengthExceeded {
+            length: 3,
+            limit: 2
+        }
+    );
+
+    // Header of a 1 GiB string, rejected before reading the payload
+    let data = [0xbb, 0x40, 0x00, 0x00, 0x00, 0x61];
+    assert_eq!(
+        from_reader::<_, String>(&data[..]).unwrap_err().into_kind(),
+        ErrorKind::LengthExceeded {
+            length: 0x4000_0000,
+            limit: DEFAULT_MAX_LENGTH
+        }
+    );
+    let mut stream = StreamDeserializer::<_, String>::with_options(
+        &data[..],
+        DecodeOptions::new().max_length(0x4000_0000),
+    );
+    assert_eq!(
+        stream.next().unwrap().unwrap_err().into_kind(),
+        ErrorKind::UnexpectedEof {
+            missing: 0x4000_0000 - 1
+        }
+    );
+}
+
+#[test]
+fn list_elements_are_limited() {
+    // [[1], [2]] holds four elements in total
+    let data = [0xc4, 0xc1, 0x01, 0xc1, 0x02];
+    let mut deserializer = Deserializer::with_options(&data, DecodeOptions::new().max_elements(3));
+    assert_eq!(
+        Vec::<Vec<u8>>::deserialize(&mut deserializer)
+            .unwrap_err()
+            .into_kind(),
+        ErrorKind::ElementsExceeded { limit: 3 }
+    );
+    let mut deserializer = Deserializer::with_options(&data, DecodeOptions::new().max_elements(4));
+    assert_eq!(
+        Vec::<Vec<u8>>::deserialize(&mut deserializer).unwrap(),
+        vec![vec![1], vec![2]]
+    );
+
+    let mut stream = StreamDeserializer::<_, Vec<u8>>::with_options(
+        &[0xc1, 0x01, 0xc2, 0x01, 0x02][..],
+        DecodeOptions::new().max_elements(1),
+    );
+    assert_eq!(stream.next().unwrap().unwrap(), vec![1]);
+    assert_eq!(
+        stream.next().unwrap().unwrap_err().into_kind(),
+        ErrorKind::ElementsExceeded { limit: 1 }
+    );
+}
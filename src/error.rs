@@ -8,13 +8,35 @@
 
 use std;
 use std::fmt::{self, Display};
+use std::io;
+use std::sync::Arc;
 
 use serde::{de, ser};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Error raised while encoding or decoding RLP. Decoding errors also tell
+/// where the input went wrong.
 #[derive(Clone, Debug, PartialEq)]
-pub enum Error {
+pub struct Error {
+    kind: ErrorKind,
+    position: Option<Position>,
+}
+
+/// Where in the input a decoding error happened.
+#[derive(Clone, Debug, PartialEq)]
+struct Position {
+    offset: usize,
+    path: String,
+}
+
+/// What went wrong.
+///
+/// More variants may be added in minor releases, so matches need a wildcard
+/// arm.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
     Message(String),
     TrailingBytes,
     EmptyBuffer,
@@ -23,39 +45,251 @@ pub enum Error {
     ExpectedList,
     ExpectedString,
     InvalidString,
-    WrongPrefix,
+    InvalidBool,
+    /// Integer does not fit into the `target` type.
+    IntegerOverflow {
+        target: &'static str,
+    },
+    /// Item is not encoded the shortest possible way, rejected in strict mode.
+    NonCanonical(NonCanonical),
+    LengthPrefixTruncated,
+    LengthOverflow,
+    /// Input ended `missing` bytes before the end of the item.
+    UnexpectedEof {
+        missing: usize,
+    },
+    /// Lists are nested deeper than `limit`.
+    DepthExceeded {
+        limit: usize,
+    },
+    /// Item of `length` bytes is longer than `limit`.
+    LengthExceeded {
+        length: usize,
+        limit: usize,
+    },
+    /// Lists hold more than `limit` elements in total.
+    ElementsExceeded {
+        limit: usize,
+    },
+    /// List of `count` items has no item at `index`.
+    IndexOutOfBounds {
+        index: usize,
+        count: usize,
+    },
+    /// Type of the value can not be represented in RLP, like floats.
+    UnsupportedType(&'static str),
+    LengthMismatch {
+        expected: String,
+        found: usize,
+    },
+    Io(IoError),
+}
+
+/// Ways an item can be encoded other than the shortest possible one.
+///
+/// More reasons may be added in minor releases, so matches need a wildcard
+/// arm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum NonCanonical {
+    /// Integer has leading zero bytes, or a redundant sign byte.
+    IntegerPadding,
+    /// Single byte below 0x80 is wrapped in a string header.
+    SingleByteString,
+    /// Long form length prefix is used for an item that fits the short form.
+    LongForm,
+    /// Length prefix has leading zero bytes.
+    LengthPadding,
+}
+
+impl Display for NonCanonical {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(match *self {
+            NonCanonical::IntegerPadding => "integer has leading zero bytes",
+            NonCanonical::SingleByteString => "single byte is wrapped in a string header",
+            NonCanonical::LongForm => "long form length is used for a short item",
+            NonCanonical::LengthPadding => "length prefix has leading zero bytes",
+        })
+    }
+}
+
+impl Error {
+    /// What went wrong.
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn into_kind(self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Byte offset in the input of the item that failed to decode. `None` for
+    /// encoding errors.
+    pub fn offset(&self) -> Option<usize> {
+        self.position.as_ref().map(|position| position.offset)
+    }
+
+    /// Path of the item that failed to decode, like `transactions[17].value`.
+    /// It is empty for the top level item, and `None` for encoding errors.
+    pub fn path(&self) -> Option<&str> {
+        self.position.as_ref().map(|position| &position.path[..])
+    }
+
+    /// Records where the error happened, unless that is already known.
+    pub(crate) fn at(mut self, offset: usize, path: String) -> Error {
+        if self.position.is_none() {
+            self.position = Some(Position { offset, path });
+        }
+        self
+    }
+
+    /// Moves the recorded position `base` bytes further into the input.
+    pub(crate) fn shifted(mut self, base: usize) -> Error {
+        if let Some(ref mut position) = self.position {
+            position.offset += base;
+        }
+        self
+    }
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Self {
+        Error {
+            kind,
+            position: None,
+        }
+    }
+}
+
+/// An `io::Error` that can be cloned and compared, so `Error` can be as well.
+/// Two errors are equal if they are of the same kind.
+#[derive(Clone, Debug)]
+pub struct IoError(Arc<io::Error>);
+
+impl IoError {
+    pub fn kind(&self) -> io::ErrorKind {
+        self.0.kind()
+    }
+
+    pub fn get_ref(&self) -> &io::Error {
+        &self.0
+    }
+}
+
+impl PartialEq for IoError {
+    fn eq(&self, other: &IoError) -> bool {
+        self.kind() == other.kind()
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        ErrorKind::Io(IoError(Arc::new(error))).into()
+    }
 }
 
 impl ser::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
+        ErrorKind::Message(msg.to_string()).into()
     }
 }
 
 impl de::Error for Error {
     fn custom<T: Display>(msg: T) -> Self {
-        Error::Message(msg.to_string())
+        ErrorKind::Message(msg.to_string()).into()
+    }
+
+    fn invalid_length(len: usize, exp: &dyn de::Expected) -> Self {
+        ErrorKind::LengthMismatch {
+            expected: exp.to_string(),
+            found: len,
+        }
+        .into()
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorKind::Message(ref msg) => formatter.write_str(msg),
+            ErrorKind::TrailingBytes => {
+                formatter.write_str("Trailing bytes found at the end of input")
+            }
+            ErrorKind::EmptyBuffer => formatter.write_str("Empty buffer detected"),
+            ErrorKind::ListPrefixTooSmall => {
+                formatter.write_str("List prefix is bigger than the data")
+            }
+            ErrorKind::StringPrefixTooSmall => {
+                formatter.write_str("String prefix is bigger than the data")
+            }
+            ErrorKind::ExpectedList => formatter.write_str("Expected list data"),
+            ErrorKind::ExpectedString => formatter.write_str("Expected string"),
+            ErrorKind::InvalidString => formatter.write_str("Unable to decode valid string"),
+            ErrorKind::InvalidBool => formatter.write_str("Boolean is neither 0x01 nor 0x80"),
+            ErrorKind::IntegerOverflow { target } => {
+                write!(formatter, "Integer is too big for {}", target)
+            }
+            ErrorKind::NonCanonical(reason) => {
+                write!(formatter, "Non-canonical encoding, {}", reason)
+            }
+            ErrorKind::LengthPrefixTruncated => {
+                formatter.write_str("Length prefix is cut short by the end of input")
+            }
+            ErrorKind::LengthOverflow => formatter.write_str("Length is too big for this platform"),
+            ErrorKind::UnexpectedEof { missing } => write!(
+                formatter,
+                "Unexpected end of input, {} more bytes expected",
+                missing
+            ),
+            ErrorKind::DepthExceeded { limit } => {
+                write!(formatter, "Lists are nested deeper than {} levels", limit)
+            }
+            ErrorKind::LengthExceeded { length, limit } => write!(
+                formatter,
+                "Item of {} bytes is longer than the limit of {}",
+                length, limit
+            ),
+            ErrorKind::ElementsExceeded { limit } => {
+                write!(formatter, "Lists hold more than {} elements", limit)
+            }
+            ErrorKind::IndexOutOfBounds { index, count } => write!(
+                formatter,
+                "Index {} is out of bounds of a list of {} items",
+                index, count
+            ),
+            ErrorKind::UnsupportedType(name) => {
+                write!(formatter, "Type {} can not be represented in RLP", name)
+            }
+            ErrorKind::LengthMismatch {
+                ref expected,
+                found,
+            } => write!(formatter, "Invalid length {}, expected {}", found, expected),
+            ErrorKind::Io(ref error) => write!(formatter, "I/O error: {}", error.get_ref()),
+        }
     }
 }
 
 impl Display for Error {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str(std::error::Error::description(self))
+        match self.position {
+            None => write!(formatter, "{}", self.kind),
+            Some(ref position) if position.path.is_empty() => {
+                write!(formatter, "{} at offset {}", self.kind, position.offset)
+            }
+            Some(ref position) => write!(
+                formatter,
+                "{} at offset {} in {}",
+                self.kind, position.offset, position.path
+            ),
+        }
     }
 }
 
 impl std::error::Error for Error {
-    fn description(&self) -> &str {
-        match *self {
-            Error::Message(ref msg) => msg,
-            Error::TrailingBytes => "Trailing bytes found at the end of input",
-            Error::EmptyBuffer => "Empty buffer detected",
-            Error::ListPrefixTooSmall => "List prefix is bigger than the data",
-            Error::StringPrefixTooSmall => "String prefix is bigger than the data",
-            Error::ExpectedList => "Expected list data",
-            Error::ExpectedString => "Expected string",
-            Error::InvalidString => "Unable to decode valid string",
-            Error::WrongPrefix => "Wrong prefix",
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self.kind {
+            ErrorKind::Io(ref error) => Some(error.get_ref()),
+            _ => None,
         }
     }
 }
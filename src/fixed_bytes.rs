@@ -0,0 +1,97 @@
+// Copyright 2018 Althea Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fixed-size byte arrays, like 20-byte addresses and 32-byte hashes. Use it on
+//! a field with `#[serde(with = "serde_rlp::fixed_bytes")]`.
+//!
+//! Serde treats `[u8; N]` as a tuple, which would be encoded as a list of
+//! single byte items. This encodes the array as one RLP string instead, and
+//! rejects strings of any other length on decode.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+
+pub fn serialize<S, const N: usize>(value: &[u8; N], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_bytes(value)
+}
+
+pub fn deserialize<'de, D, const N: usize>(deserializer: D) -> Result<[u8; N], D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(FixedBytesVisitor::<N>)
+}
+
+struct FixedBytesVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for FixedBytesVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes", N)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<[u8; N], E> {
+        if v.len() != N {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        let mut bytes = [0u8; N];
+        bytes.copy_from_slice(v);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Transfer {
+    #[serde(with = "::fixed_bytes")]
+    to: [u8; 20],
+    #[serde(with = "::fixed_bytes")]
+    hash: [u8; 32],
+}
+
+#[test]
+fn fixed_bytes_roundtrip() {
+    use de::from_bytes;
+    use ser::to_bytes;
+
+    let transfer = Transfer {
+        to: [0x11; 20],
+        hash: [0x22; 32],
+    };
+    let data = to_bytes(&transfer).unwrap();
+    let mut expected = vec![0xf6, 0x94];
+    expected.extend_from_slice(&[0x11; 20]);
+    expected.push(0xa0);
+    expected.extend_from_slice(&[0x22; 32]);
+    assert_eq!(data, expected);
+    assert_eq!(from_bytes::<Transfer>(&data).unwrap(), transfer);
+}
+
+#[test]
+fn fixed_bytes_wrong_length() {
+    use de::from_bytes;
+    use error::ErrorKind;
+
+    let mut data = vec![0xf5, 0x93];
+    data.extend_from_slice(&[0x11; 19]);
+    data.push(0xa0);
+    data.extend_from_slice(&[0x22; 32]);
+    assert_eq!(
+        from_bytes::<Transfer>(&data).unwrap_err().into_kind(),
+        ErrorKind::LengthMismatch {
+            expected: "20 bytes".to_string(),
+            found: 19
+        }
+    );
+}
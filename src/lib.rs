@@ -6,14 +6,28 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-extern crate byteorder;
 extern crate num;
 extern crate serde;
 
+pub mod config;
 pub mod de;
 mod error;
+pub mod fixed_bytes;
+// Low level helpers are only public to be reachable from fuzz targets
+#[cfg(fuzzing)]
+pub mod rlp;
+#[cfg(not(fuzzing))]
 mod rlp;
 pub mod ser;
+pub mod u256;
+pub mod value;
+pub mod view;
+
+pub use error::{Error, ErrorKind, IoError, NonCanonical, Result};
+pub use view::Rlp;
 
 #[cfg(test)]
 extern crate serde_bytes;
+#[cfg(test)]
+#[macro_use]
+extern crate serde_derive;
@@ -6,120 +6,272 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use byteorder::{BigEndian, WriteBytesExt};
-use error::Error;
+use error::{Error, ErrorKind, NonCanonical};
 use num::Num;
 use num::Unsigned;
 use std::mem::size_of;
+use std::ops::Deref;
 
-fn to_binary(x: u64) -> Vec<u8> {
-    if x == 0 {
-        Vec::new()
-    } else {
-        let mut result = to_binary(x / 256);
-        result.push((x % 256) as u8);
-        result
-    }
+/// Short piece of encoded data, like a length prefix or an integer. It is kept
+/// on the stack to avoid an allocation for every serialized item.
+pub struct ShortBytes {
+    bytes: [u8; 16],
+    len: usize,
 }
 
-#[test]
-fn test_to_binary_null() {
-    assert_eq!(to_binary(0u64), []);
-}
+impl Deref for ShortBytes {
+    type Target = [u8];
 
-#[test]
-fn test_to_binary_non_null() {
-    assert_eq!(to_binary(1024u64), [0x04, 0x00]);
-    assert_eq!(
-        to_binary(18446744073709551615u64),
-        [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
-    );
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
 }
 
-pub fn encode_length(l: u64, offset: u8) -> Vec<u8> {
+pub fn encode_length(l: u64, offset: u8) -> ShortBytes {
+    let mut bytes = [0u8; 16];
     if l < 56 {
-        vec![l as u8 + offset]
-    } else if l < u64::max_value() {
-        let mut bl = to_binary(l);
-        let magic = bl.len() as u8 + offset + 55;
-        bl.insert(0, magic);
-        bl
+        bytes[0] = l as u8 + offset;
+        ShortBytes { bytes, len: 1 }
     } else {
-        panic!("input too long");
+        // Length itself goes after the prefix byte, as a number. It takes at
+        // most 8 bytes, which is the most the prefix can announce.
+        let bl = encode_number(l);
+        bytes[0] = bl.len() as u8 + offset + 55;
+        bytes[1..=bl.len()].copy_from_slice(&bl);
+        ShortBytes {
+            bytes,
+            len: 1 + bl.len(),
+        }
     }
 }
 
 #[test]
 fn test_encode_length_small() {
-    assert_eq!(encode_length(55u64, 0xc0), [55 + 0xc0]);
+    assert_eq!(*encode_length(55u64, 0xc0), [55 + 0xc0]);
+}
+
+#[test]
+fn test_encode_length_medium() {
+    assert_eq!(*encode_length(56u64, 0x80), [0xb8, 0x38]);
+    assert_eq!(*encode_length(1024u64, 0xc0), [0xf9, 0x04, 0x00]);
 }
 
 #[test]
 fn test_encode_length_big() {
     assert_eq!(
-        encode_length(18446744073709551614u64, 0x80),
+        *encode_length(18446744073709551614u64, 0x80),
         [191, 255, 255, 255, 255, 255, 255, 255, 254]
     );
 }
 
 #[test]
-#[should_panic]
-fn test_encode_length_of_wrong_size() {
-    encode_length(18446744073709551615u64, 0x80);
+fn test_encode_length_max() {
+    assert_eq!(
+        *encode_length(u64::MAX, 0x80),
+        [0xbf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
+    assert_eq!(
+        *encode_length(u64::MAX, 0xc0),
+        [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]
+    );
 }
 
-pub fn encode_number<T: Num + Unsigned>(v: T) -> Vec<u8>
+pub fn encode_number<T>(v: T) -> ShortBytes
 where
-    T: Into<u64>,
+    T: Num + Unsigned + Into<u128>,
 {
-    let mut wtr = vec![];
-    wtr.write_uint::<BigEndian>(v.into(), size_of::<T>())
-        .unwrap();
-    let index = wtr.iter().position(|&r| r > 0u8).unwrap_or(0);
-    wtr.split_off(index)
+    let v = v.into();
+    // Canonical integers have no leading zero bytes, so zero is an empty string.
+    let len = size_of::<u128>() - (v.leading_zeros() / 8) as usize;
+    let mut bytes = [0u8; 16];
+    bytes[..len].copy_from_slice(&v.to_be_bytes()[size_of::<u128>() - len..]);
+    ShortBytes { bytes, len }
 }
 
 #[test]
 fn test_encode_number() {
-    assert_eq!(encode_number(255u8), [0xff]);
-    assert_eq!(encode_number(1024u16), [0x04, 0x00]);
-    assert_eq!(encode_number(1024u32), [0x04, 0x00]);
-    assert_eq!(encode_number(1024u64), [0x04, 0x00]);
+    assert_eq!(*encode_number(255u8), [0xff]);
+    assert_eq!(*encode_number(1024u16), [0x04, 0x00]);
+    assert_eq!(*encode_number(1024u32), [0x04, 0x00]);
+    assert_eq!(*encode_number(1024u64), [0x04, 0x00]);
+    assert_eq!(*encode_number(u128::MAX), [0xff; 16]);
 }
 
-fn to_integer(b: &[u8]) -> Option<u64> {
-    if b.len() == 0 {
-        None
-    } else if b.len() == 1 {
-        Some(b[0] as u64)
-    } else {
-        return Some(b[b.len() - 1] as u64 + to_integer(&b[0..b.len() - 1]).unwrap() * 256);
+#[test]
+fn test_encode_number_zero() {
+    assert_eq!(*encode_number(0u8), []);
+    assert_eq!(*encode_number(0u32), []);
+    assert_eq!(*encode_number(0u64), []);
+}
+
+/// Decodes big-endian bytes of an RLP string into an integer. Empty input is zero.
+pub fn decode_number(v: &[u8]) -> Result<u64, Error> {
+    if v.len() > size_of::<u64>() {
+        return Err(ErrorKind::IntegerOverflow { target: "u64" }.into());
     }
+    Ok(v.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b)))
+}
+
+/// Same as `decode_number`, for integers up to 128 bits.
+pub fn decode_u128(v: &[u8]) -> Result<u128, Error> {
+    if v.len() > size_of::<u128>() {
+        return Err(ErrorKind::IntegerOverflow { target: "u128" }.into());
+    }
+    Ok(v.iter().fold(0u128, |acc, &b| (acc << 8) | u128::from(b)))
 }
 
 #[test]
-fn to_integer_with_empty_buffer() {
-    assert!(to_integer(&[]).is_none());
+fn test_decode_u128() {
+    assert_eq!(decode_u128(&[]).unwrap(), 0);
+    assert_eq!(decode_u128(&[0x04, 0x00]).unwrap(), 1024);
+    assert_eq!(decode_u128(&[0xff; 16]).unwrap(), u128::MAX);
+    assert_eq!(
+        decode_u128(&[0x01; 17]),
+        Err(ErrorKind::IntegerOverflow { target: "u128" }.into())
+    );
 }
 
 #[test]
-fn to_integer_with_single_byte() {
-    assert_eq!(to_integer(&[0xffu8]).unwrap(), 255u64);
+fn test_decode_number() {
+    assert_eq!(decode_number(&[]).unwrap(), 0);
+    assert_eq!(decode_number(&[0xff]).unwrap(), 255);
+    assert_eq!(decode_number(&[0x04, 0x00]).unwrap(), 1024);
+    assert_eq!(decode_number(&[0xff; 8]).unwrap(), u64::MAX);
 }
 
 #[test]
-fn to_integer_with_multiple_bytes() {
-    assert_eq!(to_integer(&[0x04u8, 0x00u8]).unwrap(), 1024u64);
+fn test_decode_number_overflow() {
+    assert_eq!(
+        decode_number(&[0x01; 9]),
+        Err(ErrorKind::IntegerOverflow { target: "u64" }.into())
+    );
+}
+
+#[test]
+fn decode_number_with_multiple_bytes() {
+    assert_eq!(decode_number(&[0x04u8, 0x00u8]).unwrap(), 1024u64);
 }
 
 #[test]
 fn decode_u32_max() {
-    assert_eq!(to_integer(&[0xffu8; 4]).unwrap(), 4294967295u64);
+    assert_eq!(decode_number(&[0xffu8; 4]).unwrap(), 4294967295u64);
 }
 
 #[test]
 fn decode_u64_max() {
-    assert_eq!(to_integer(&[0xffu8; 8]).unwrap(), 18446744073709551615u64);
+    assert_eq!(
+        decode_number(&[0xffu8; 8]).unwrap(),
+        18446744073709551615u64
+    );
+}
+
+/// Maps a signed integer to an unsigned one, keeping small magnitudes small.
+pub fn zigzag_encode(v: i128) -> u128 {
+    ((v << 1) ^ (v >> 127)) as u128
+}
+
+pub fn zigzag_decode(v: u128) -> i128 {
+    (v >> 1) as i128 ^ -((v & 1) as i128)
+}
+
+#[test]
+fn test_zigzag() {
+    let pairs = [
+        (0, 0),
+        (-1, 1),
+        (1, 2),
+        (-2, 3),
+        (i128::from(i64::MAX), u128::from(u64::MAX - 1)),
+        (i128::from(i64::MIN), u128::from(u64::MAX)),
+        (i128::MAX, u128::MAX - 1),
+        (i128::MIN, u128::MAX),
+    ];
+    for &(signed, unsigned) in &pairs {
+        assert_eq!(zigzag_encode(signed), unsigned);
+        assert_eq!(zigzag_decode(unsigned), signed);
+    }
+}
+
+/// Encodes a signed integer as big-endian two's complement, dropping leading
+/// bytes that only repeat the sign. Zero is empty.
+pub fn encode_twos_complement(v: i128) -> ShortBytes {
+    let be = v.to_be_bytes();
+    let mut start = 0;
+    while start < be.len() - 1 && is_sign_byte(be[start], be[start + 1]) {
+        start += 1;
+    }
+    if v == 0 {
+        start = be.len();
+    }
+    let mut bytes = [0u8; 16];
+    bytes[..be.len() - start].copy_from_slice(&be[start..]);
+    ShortBytes {
+        bytes,
+        len: be.len() - start,
+    }
+}
+
+/// Whether `byte` is redundant in front of `next`, because sign extension of
+/// `next` would produce it anyway.
+fn is_sign_byte(byte: u8, next: u8) -> bool {
+    (byte == 0x00 && next < 0x80) || (byte == 0xff && next >= 0x80)
+}
+
+/// Decodes big-endian two's complement bytes. Empty input is zero.
+pub fn decode_twos_complement(v: &[u8]) -> Result<i128, Error> {
+    if v.len() > size_of::<i128>() {
+        return Err(ErrorKind::IntegerOverflow { target: "i128" }.into());
+    }
+    let sign = if v.first().is_some_and(|&b| b >= 0x80) {
+        -1
+    } else {
+        0
+    };
+    Ok(v.iter().fold(sign, |acc, &b| (acc << 8) | i128::from(b)))
+}
+
+/// Whether `v` is the shortest two's complement encoding of its value.
+pub fn is_canonical_twos_complement(v: &[u8]) -> bool {
+    match v.len() {
+        0 => true,
+        1 => v[0] != 0,
+        _ => !is_sign_byte(v[0], v[1]),
+    }
+}
+
+#[test]
+fn test_twos_complement() {
+    let pairs: &[(i128, &[u8])] = &[
+        (0, &[]),
+        (1, &[0x01]),
+        (127, &[0x7f]),
+        (128, &[0x00, 0x80]),
+        (1024, &[0x04, 0x00]),
+        (-1, &[0xff]),
+        (-128, &[0x80]),
+        (-129, &[0xff, 0x7f]),
+        (
+            i128::from(i64::MAX),
+            &[0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+        ),
+        (i128::from(i64::MIN), &[0x80, 0, 0, 0, 0, 0, 0, 0]),
+        (
+            i128::MIN,
+            &[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ),
+    ];
+    for &(v, bytes) in pairs {
+        assert_eq!(*encode_twos_complement(v), *bytes);
+        assert_eq!(decode_twos_complement(bytes).unwrap(), v);
+        assert!(is_canonical_twos_complement(bytes));
+    }
+    assert!(!is_canonical_twos_complement(&[0x00]));
+    assert!(!is_canonical_twos_complement(&[0x00, 0x7f]));
+    assert!(!is_canonical_twos_complement(&[0xff, 0x80]));
+    assert_eq!(
+        decode_twos_complement(&[0x01; 17]),
+        Err(ErrorKind::IntegerOverflow { target: "i128" }.into())
+    );
 }
 
 #[derive(Debug, PartialEq)]
@@ -137,61 +289,127 @@ pub struct DecodeLengthResult {
     pub expected_type: ExpectedType,
 }
 
-/// Decodes chunk of data and outputs offset, length of nested data and its expected type
+/// Number of bytes taken by the header of an item starting with `prefix`,
+/// including the prefix itself. Single bytes below 0x80 have no header.
+pub fn header_size(prefix: u8) -> usize {
+    match prefix {
+        0x00..=0x7f => 0,
+        0x80..=0xb7 | 0xc0..=0xf7 => 1,
+        0xb8..=0xbf => 1 + (prefix - 0xb7) as usize,
+        0xf8..=0xff => 1 + (prefix - 0xf7) as usize,
+    }
+}
+
+/// Decodes the header at the start of `input` without requiring the payload
+/// to follow it. Used when the payload still has to be read from a stream.
+pub fn decode_header(input: &[u8]) -> Result<DecodeLengthResult, Error> {
+    let prefix = *input.first().ok_or(ErrorKind::EmptyBuffer)?;
+    let offset = header_size(prefix);
+    if input.len() < offset {
+        return Err(ErrorKind::LengthPrefixTruncated.into());
+    }
+    let (length, expected_type) = match prefix {
+        0x00..=0x7f => (1, ExpectedType::StringType),
+        0x80..=0xb7 => ((prefix - 0x80) as usize, ExpectedType::StringType),
+        0xc0..=0xf7 => ((prefix - 0xc0) as usize, ExpectedType::ListType),
+        0xb8..=0xbf => (long_length(&input[1..offset])?, ExpectedType::StringType),
+        0xf8..=0xff => (long_length(&input[1..offset])?, ExpectedType::ListType),
+    };
+    Ok(DecodeLengthResult {
+        offset,
+        length,
+        expected_type,
+    })
+}
+
+/// Decodes the length that follows a long form prefix. It is at most 8 bytes
+/// long, but may still not fit into `usize` on 32 bit targets.
+fn long_length(bytes: &[u8]) -> Result<usize, Error> {
+    let length = decode_number(bytes)?;
+    if length > usize::MAX as u64 {
+        return Err(ErrorKind::LengthOverflow.into());
+    }
+    Ok(length as usize)
+}
+
+#[test]
+fn decode_header_without_payload() {
+    let res = decode_header(&[0x83]).unwrap();
+    assert_eq!((res.offset, res.length), (1, 3));
+    assert_eq!(res.expected_type, ExpectedType::StringType);
+
+    let res = decode_header(&[0xf9, 0x01, 0x4c]).unwrap();
+    assert_eq!((res.offset, res.length), (3, 332));
+    assert_eq!(res.expected_type, ExpectedType::ListType);
+
+    let res = decode_header(&[0x2a]).unwrap();
+    assert_eq!((res.offset, res.length), (0, 1));
+}
+
+#[test]
+fn decode_header_truncated() {
+    assert_eq!(
+        decode_header(&[]).unwrap_err().into_kind(),
+        ErrorKind::EmptyBuffer
+    );
+    assert_eq!(
+        decode_header(&[0xb9, 0x01]).unwrap_err().into_kind(),
+        ErrorKind::LengthPrefixTruncated
+    );
+}
+
+/// Decodes chunk of data and outputs offset, length of nested data and its
+/// expected type. The whole item is guaranteed to fit in `input`.
 pub fn decode_length(input: &[u8]) -> Result<DecodeLengthResult, Error> {
-    if input.len() == 0 {
-        return Err(Error::EmptyBuffer);
-    }
-    let prefix = input[0];
-    if prefix <= 0x7f {
-        Ok(DecodeLengthResult {
-            offset: 0,
-            length: 1usize,
-            expected_type: ExpectedType::StringType,
-        })
-    } else if prefix <= 0xb7 && input.len() > (prefix - 0x80) as usize {
-        let str_len = prefix - 0x80;
-        Ok(DecodeLengthResult {
-            offset: 1,
-            length: str_len as usize,
-            expected_type: ExpectedType::StringType,
-        })
-    } else if prefix <= 0xbf
-        && input.len() > prefix.checked_sub(0xb7).ok_or(Error::WrongPrefix)? as usize
-        && input.len() as u64
-            > prefix as u64 - 0xb7u64 + to_integer(&input[1..prefix as usize - 0xb7 + 1])
-                .ok_or(Error::StringPrefixTooSmall)?
-    {
-        let len_of_str_len = prefix as usize - 0xb7;
-        let str_len = to_integer(&input[1..len_of_str_len + 1]).unwrap();
-        Ok(DecodeLengthResult {
-            offset: 1 + len_of_str_len,
-            length: str_len as usize,
-            expected_type: ExpectedType::StringType,
-        })
-    } else if prefix <= 0xf7 && input.len() > prefix as usize - 0xc0 {
-        let list_len = prefix as usize - 0xc0;
-        Ok(DecodeLengthResult {
-            offset: 1,
-            length: list_len,
-            expected_type: ExpectedType::ListType,
-        })
-    } else if
-    /* prefix <= 0xff && */
-    input.len() as u64 > prefix as u64 - 0xf7
-        && input.len() as u64
-            > prefix as u64 - 0xf7u64 + to_integer(&input[1..prefix as usize - 0xf7 + 1])
-                .ok_or(Error::ListPrefixTooSmall)?
-    {
-        let len_of_list_len = prefix as usize - 0xf7;
-        let list_len = to_integer(&input[1..len_of_list_len + 1]).unwrap();
-        Ok(DecodeLengthResult {
-            offset: 1 + len_of_list_len,
-            length: list_len as usize,
-            expected_type: ExpectedType::ListType,
-        })
-    } else {
-        unreachable!();
+    let res = decode_header(input)?;
+    // The header is known to fit, so this can't underflow
+    if res.length > input.len() - res.offset {
+        return Err(match res.expected_type {
+            ExpectedType::StringType => ErrorKind::StringPrefixTooSmall.into(),
+            ExpectedType::ListType => ErrorKind::ListPrefixTooSmall.into(),
+        });
+    }
+    Ok(res)
+}
+
+#[test]
+fn decode_truncated_items() {
+    let cases: &[(&[u8], ErrorKind)] = &[
+        (&[0x83, 0x61], ErrorKind::StringPrefixTooSmall),
+        (&[0xb8], ErrorKind::LengthPrefixTruncated),
+        (&[0xb8, 0x38, 0x00], ErrorKind::StringPrefixTooSmall),
+        (&[0xc3, 0x01], ErrorKind::ListPrefixTooSmall),
+        (&[0xf8], ErrorKind::LengthPrefixTruncated),
+        (&[0xfa, 0x00, 0x01], ErrorKind::LengthPrefixTruncated),
+        (
+            &[0xfb, 0xff, 0xff, 0xff, 0xff, 0x00],
+            ErrorKind::ListPrefixTooSmall,
+        ),
+    ];
+    for &(input, ref error) in cases {
+        assert_eq!(
+            decode_length(input).unwrap_err().into_kind(),
+            *error,
+            "{:?}",
+            input
+        );
+    }
+    // Either too long for the input or for the address space
+    assert!(decode_length(&[0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00]).is_err());
+}
+
+#[test]
+fn decode_length_stays_in_bounds() {
+    // Every prefix followed by every possible byte, and by nothing
+    for prefix in 0..=0xffu8 {
+        for input in (0..=0xffu8)
+            .map(|b| vec![prefix, b])
+            .chain(Some(vec![prefix]))
+        {
+            if let Ok(res) = decode_length(&input) {
+                assert!(res.offset + res.length <= input.len(), "{:?}", input);
+            }
+        }
     }
 }
 
@@ -212,7 +430,7 @@ fn decode_single_byte() {
 #[test]
 fn decode_short_string() {
     // "abc"
-    let input = vec![0x83, 0x61, 0x62, 0x63, 0xff];
+    let input = [0x83, 0x61, 0x62, 0x63, 0xff];
     let res = decode_length(&input[..]).unwrap();
     assert_eq!(res.offset, 1);
     assert_eq!(res.length, 3);
@@ -227,3 +445,71 @@ fn decode_short_array() {
     assert_eq!(res.length, 4);
     assert_eq!(res.expected_type, ExpectedType::ListType);
 }
+
+/// Checks that the header described by `res` is the shortest possible encoding
+/// of its item, as required by Ethereum consensus rules.
+pub fn check_canonical_length(input: &[u8], res: &DecodeLengthResult) -> Result<(), Error> {
+    if res.offset == 1
+        && res.length == 1
+        && res.expected_type == ExpectedType::StringType
+        && input[1] < 0x80
+    {
+        // Single byte below 0x80 has to be encoded as itself
+        return Err(ErrorKind::NonCanonical(NonCanonical::SingleByteString).into());
+    }
+    if res.offset > 1 && input[1] == 0 {
+        return Err(ErrorKind::NonCanonical(NonCanonical::LengthPadding).into());
+    }
+    if res.offset > 1 && res.length < 56 {
+        // Long form used for data that fits the short form
+        return Err(ErrorKind::NonCanonical(NonCanonical::LongForm).into());
+    }
+    Ok(())
+}
+
+#[test]
+fn canonical_lengths() {
+    for input in &[&[0x05][..], &[0x80], &[0x81, 0x80], &[0xc0], &[0xc1, 0x05]] {
+        let res = decode_length(input).unwrap();
+        assert!(check_canonical_length(input, &res).is_ok());
+    }
+}
+
+#[test]
+fn non_canonical_single_byte() {
+    let input = [0x81, 0x05];
+    let res = decode_length(&input).unwrap();
+    assert_eq!(
+        check_canonical_length(&input, &res),
+        Err(ErrorKind::NonCanonical(NonCanonical::SingleByteString).into())
+    );
+}
+
+#[test]
+fn non_canonical_long_form() {
+    let mut input = vec![0xb8, 0x05];
+    input.extend(&[0x61; 5]);
+    let res = decode_length(&input).unwrap();
+    assert_eq!(
+        check_canonical_length(&input, &res),
+        Err(ErrorKind::NonCanonical(NonCanonical::LongForm).into())
+    );
+    let mut input = vec![0xf8, 0x02, 0x01, 0x02];
+    input.extend(&[0x61; 2]);
+    let res = decode_length(&input).unwrap();
+    assert_eq!(
+        check_canonical_length(&input, &res),
+        Err(ErrorKind::NonCanonical(NonCanonical::LongForm).into())
+    );
+}
+
+#[test]
+fn non_canonical_leading_zero_length() {
+    let mut input = vec![0xb9, 0x00, 0x38];
+    input.extend(&[0x61; 56]);
+    let res = decode_length(&input).unwrap();
+    assert_eq!(
+        check_canonical_length(&input, &res),
+        Err(ErrorKind::NonCanonical(NonCanonical::LengthPadding).into())
+    );
+}
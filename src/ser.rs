@@ -6,48 +6,266 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
-use error::{Error, Result};
+use config::{OptionEncoding, SignedEncoding, StructEncoding, VariantTag};
+use error::{Error, ErrorKind, Result};
 use rlp;
 use serde::ser::{self, Serialize};
-use std::collections::VecDeque;
+use std::io;
 use std::marker::Sized;
+use std::mem;
 
+/// Serializes values to RLP with configurable encoding conventions.
+#[derive(Clone, Debug, Default)]
 pub struct Serializer {
-    // This is a vector of bytes that starts empty and bytes of RLP is appended as
-    // values are serialized.
-    output: Vec<u8>,
-    // When going deeper into the structure (i.e. when processing vector of vectors)
-    // this buffer is used to keep track of state "before" going deeper.
-    // This way we can save the state, and serialize nested sequence alone, and then
-    // once we're done with that sequence, we can go back to the saved state.
-    buffer: VecDeque<Vec<u8>>,
+    /// Layout used for structs
+    struct_encoding: StructEncoding,
+    /// Identifier written at the head of enum variants
+    variant_tag: VariantTag,
+    /// Representation of signed integers
+    signed_encoding: SignedEncoding,
+    /// Representation of optional values
+    option_encoding: OptionEncoding,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Serializer::default()
+    }
+
+    /// Sets the layout of structs. Defaults to `StructEncoding::List`.
+    pub fn struct_encoding(mut self, encoding: StructEncoding) -> Self {
+        self.struct_encoding = encoding;
+        self
+    }
+
+    /// Sets how enum variants are identified. Defaults to `VariantTag::Name`.
+    pub fn variant_tag(mut self, tag: VariantTag) -> Self {
+        self.variant_tag = tag;
+        self
+    }
+
+    /// Sets how signed integers are represented. Defaults to
+    /// `SignedEncoding::Reject`.
+    pub fn signed_encoding(mut self, encoding: SignedEncoding) -> Self {
+        self.signed_encoding = encoding;
+        self
+    }
+
+    /// Sets how `Option` values are represented. Defaults to
+    /// `OptionEncoding::EmptyString`.
+    pub fn option_encoding(mut self, encoding: OptionEncoding) -> Self {
+        self.option_encoding = encoding;
+        self
+    }
+
+    /// Serializes `value` with the current settings and returns the RLP bytes.
+    pub fn to_bytes<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: ?Sized + Serialize,
+    {
+        let (lengths, size) = self.measure(value)?;
+        let mut output = Vec::with_capacity(size);
+        let mut encoder = Encoder::new(self, &mut output, Some(lengths));
+        value.serialize(&mut encoder)?;
+        encoder.flush_nones()?;
+        Ok(output)
+    }
+
+    /// Serializes `value` with the current settings straight into `writer`,
+    /// which should be buffered, see `to_writer`.
+    pub fn to_writer<W, T>(&self, writer: W, value: &T) -> Result<()>
+    where
+        W: io::Write,
+        T: ?Sized + Serialize,
+    {
+        let (lengths, _) = self.measure(value)?;
+        let mut encoder = Encoder::new(self, writer, Some(lengths));
+        value.serialize(&mut encoder)?;
+        encoder.flush_nones()
+    }
+
+    /// Runs the measuring pass over `value`. Returns the payload length of every
+    /// list and the total size of the output.
+    fn measure<T>(&self, value: &T) -> Result<(Vec<usize>, usize)>
+    where
+        T: ?Sized + Serialize,
+    {
+        // RLP puts the length of a list in front of its items, so the first pass
+        // only measures every list, and the second one writes the output knowing
+        // all the lengths up front. This way every byte is written exactly once.
+        let mut encoder = Encoder::new(self, io::sink(), None);
+        value.serialize(&mut encoder)?;
+        encoder.flush_nones()?;
+        Ok((encoder.lengths, encoder.written))
+    }
+}
+
+/// Does the actual serialization for a `Serializer`, in one of two passes over
+/// the value.
+struct Encoder<'a, W> {
+    config: &'a Serializer,
+    output: W,
+    /// Whether this pass only measures lengths of lists
+    measuring: bool,
+    /// Number of bytes produced so far
+    written: usize,
+    /// Payload length of every list, in the order lists are opened. These are
+    /// collected by the measuring pass, and used by the writing pass.
+    lengths: Vec<usize>,
+    /// Position in `lengths` of the next list to open
+    next_list: usize,
+    /// Lists that are still open, as their position in `lengths` and the number
+    /// of bytes written before their first item
+    open_lists: Vec<(usize, usize)>,
+    /// Number of `None` values not written yet, with `OptionEncoding::Trailing`
+    pending_nones: usize,
+}
+
+impl<'a, W> Encoder<'a, W>
+where
+    W: io::Write,
+{
+    /// Creates a measuring encoder if `lengths` are not known yet, or a writing
+    /// encoder otherwise.
+    fn new(config: &'a Serializer, output: W, lengths: Option<Vec<usize>>) -> Self {
+        Encoder {
+            config,
+            output,
+            measuring: lengths.is_none(),
+            written: 0,
+            lengths: lengths.unwrap_or_default(),
+            next_list: 0,
+            open_lists: Vec::new(),
+            pending_nones: 0,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<()> {
+        if self.pending_nones > 0 {
+            self.flush_nones()?;
+        }
+        self.write_raw(bytes)
+    }
+
+    fn write_raw(&mut self, bytes: &[u8]) -> Result<()> {
+        if !self.measuring {
+            self.output.write_all(bytes)?;
+        }
+        self.written += bytes.len();
+        Ok(())
+    }
+
+    /// Writes the `None` values held back so far. They turned out not to be
+    /// trailing, as something else follows them.
+    fn flush_nones(&mut self) -> Result<()> {
+        for _ in 0..mem::take(&mut self.pending_nones) {
+            self.write_raw(&[0x80])?;
+        }
+        Ok(())
+    }
+
+    /// Starts a nested list.
+    fn begin_list(&mut self) -> Result<()> {
+        self.flush_nones()?;
+        let index = self.next_list;
+        self.next_list += 1;
+        if self.measuring {
+            self.lengths.push(0);
+        } else {
+            // Both passes see the same value, so every list was measured before
+            let length = *self.lengths.get(index).ok_or_else(|| {
+                <Error as ser::Error>::custom("value serialized differently in the measuring pass")
+            })?;
+            self.write(&rlp::encode_length(length as u64, 0xc0))?;
+        }
+        self.open_lists.push((index, self.written));
+        Ok(())
+    }
+
+    /// Finishes the list started by the matching `begin_list`.
+    fn end_list(&mut self) -> Result<()> {
+        self.flush_nones()?;
+        let (index, start) = self.open_lists.pop().unwrap(); // This unwrap is safe assuming the normal path of the code.
+        let length = self.written - start;
+        if self.measuring {
+            self.lengths[index] = length;
+            // Prefix is written in front of the items in the writing pass
+            self.written += rlp::encode_length(length as u64, 0xc0).len();
+        } else if self.lengths[index] != length {
+            return Err(ser::Error::custom(
+                "value serialized differently in the measuring pass",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Opens the list of an enum variant and writes its tag.
+    fn begin_variant(&mut self, variant_index: u32, variant: &'static str) -> Result<()> {
+        self.begin_list()?;
+        match self.config.variant_tag {
+            VariantTag::Name => variant.serialize(self),
+            VariantTag::Index => variant_index.serialize(self),
+        }
+    }
+
+    /// Finishes a struct or a struct variant. Trailing `None` fields are left
+    /// out of positional structs.
+    fn end_struct(&mut self) -> Result<()> {
+        if self.config.struct_encoding == StructEncoding::List {
+            self.pending_nones = 0;
+        }
+        self.end_list()
+    }
+
+    /// Serializes a field of a struct or a struct variant.
+    fn serialize_struct_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self.config.struct_encoding {
+            StructEncoding::List => value.serialize(self),
+            StructEncoding::KeyValue => {
+                // Serialize element of a structure as a sequence [key, value].
+                let dummy_seq = (&key, &value);
+                dummy_seq.serialize(self)
+            }
+        }
+    }
 }
 
 // By convention, the public API of a Serde deserializer is one or more `to_abc`
 // functions such as `to_string`, `to_bytes`, or `to_writer` depending on what
 // Rust types the serializer is able to produce as output.
-//
-// This basic serializer supports only `to_bytes`.
 pub fn to_bytes<T>(value: &T) -> Result<Vec<u8>>
 where
     T: Serialize,
 {
-    let mut serializer = Serializer {
-        output: Vec::new(),
-        buffer: VecDeque::new(),
-    };
-    value.serialize(&mut serializer)?;
-    Ok(serializer.output)
+    Serializer::new().to_bytes(value)
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+/// Serializes `value` into `writer` without buffering the whole output.
+///
+/// Every header and payload is written with its own `write` call, so files
+/// and sockets should be wrapped in an `io::BufWriter`.
+pub fn to_writer<W, T>(writer: W, value: &T) -> Result<()>
+where
+    W: io::Write,
+    T: ?Sized + Serialize,
+{
+    Serializer::new().to_writer(writer, value)
+}
+
+impl<'a, W> ser::Serializer for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
     // Associated types for keeping track of additional state while serializing
     // compound data structures like sequences and maps. In this case no
     // additional state is required beyond what is already stored in the
-    // Serializer struct.
+    // Encoder struct.
     type SerializeSeq = Self;
     type SerializeTuple = Self;
     type SerializeTupleStruct = Self;
@@ -56,8 +274,10 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     type SerializeStruct = Self;
     type SerializeStructVariant = Self;
 
-    fn serialize_bool(self, _v: bool) -> Result<()> {
-        unimplemented!();
+    // Same as go-ethereum: `true` is the integer 1, `false` is the integer 0,
+    // which is the empty string.
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.serialize_u8(v as u8)
     }
 
     // JSON does not distinguish between different sizes of integers, so all
@@ -76,10 +296,16 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_i64(i64::from(v))
     }
 
-    // Not particularly efficient but this is example code anyway. A more
-    // performant approach would be to use the `itoa` crate.
-    fn serialize_i64(self, _v: i64) -> Result<()> {
-        unimplemented!();
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        self.serialize_i128(i128::from(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<()> {
+        match self.config.signed_encoding {
+            SignedEncoding::Reject => Err(ErrorKind::UnsupportedType("signed integer").into()),
+            SignedEncoding::ZigZag => self.serialize_u128(rlp::zigzag_encode(v)),
+            SignedEncoding::TwosComplement => self.serialize_bytes(&rlp::encode_twos_complement(v)),
+        }
     }
 
     fn serialize_u8(self, v: u8) -> Result<()> {
@@ -98,12 +324,17 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         self.serialize_bytes(&rlp::encode_number(v))
     }
 
+    fn serialize_u128(self, v: u128) -> Result<()> {
+        self.serialize_bytes(&rlp::encode_number(v))
+    }
+
+    // RLP has no representation for floating point numbers.
     fn serialize_f32(self, _v: f32) -> Result<()> {
-        unimplemented!();
+        Err(ErrorKind::UnsupportedType("f32").into())
     }
 
     fn serialize_f64(self, _v: f64) -> Result<()> {
-        unimplemented!();
+        Err(ErrorKind::UnsupportedType("f64").into())
     }
 
     fn serialize_char(self, v: char) -> Result<()> {
@@ -112,54 +343,68 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 
     fn serialize_str(self, v: &str) -> Result<()> {
         if v.len() == 1 && v.as_bytes()[0] < 0x80 {
-            self.output.extend(v.as_bytes());
+            self.write(v.as_bytes())
         } else {
-            self.output.extend(rlp::encode_length(v.len() as u64, 0x80));
-            self.output.extend(v.as_bytes());
+            self.write(&rlp::encode_length(v.len() as u64, 0x80))?;
+            self.write(v.as_bytes())
         }
-        Ok(())
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
         // TODO: There is some duplication here that could be resolved later
         if v.len() == 1 && v[0] < 0x80 {
-            self.output.extend(v);
+            self.write(v)
         } else {
-            self.output.extend(rlp::encode_length(v.len() as u64, 0x80));
-            self.output.extend(v);
+            self.write(&rlp::encode_length(v.len() as u64, 0x80))?;
+            self.write(v)
         }
-        Ok(())
     }
 
-    // An absent optional is represented as the JSON `null`.
     fn serialize_none(self) -> Result<()> {
-        unimplemented!();
+        match self.config.option_encoding {
+            OptionEncoding::EmptyString => self.serialize_bytes(&[]),
+            OptionEncoding::List => {
+                self.begin_list()?;
+                self.end_list()
+            }
+            OptionEncoding::Trailing => {
+                // Written only if something follows in the same list
+                self.pending_nones += 1;
+                Ok(())
+            }
+        }
     }
 
     fn serialize_some<T>(self, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        // TODO: This probably can't be done better without introducing our own convention as described in RLP specification.
-        value.serialize(self)
+        match self.config.option_encoding {
+            OptionEncoding::EmptyString | OptionEncoding::Trailing => value.serialize(self),
+            OptionEncoding::List => {
+                self.begin_list()?;
+                value.serialize(&mut *self)?;
+                self.end_list()
+            }
+        }
     }
 
     fn serialize_unit(self) -> Result<()> {
-        // TODO: How to serialize unit types?
-        unimplemented!();
+        Err(ErrorKind::UnsupportedType("unit").into())
     }
 
     fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
-        unimplemented!();
+        Err(ErrorKind::UnsupportedType("unit struct").into())
     }
 
     fn serialize_unit_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
     ) -> Result<()> {
-        self.serialize_str(variant)
+        self.begin_variant(variant_index, variant)?;
+        self.end_list()
     }
 
     fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
@@ -172,27 +417,22 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_newtype_variant<T>(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
         value: &T,
     ) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
-        variant.serialize(&mut *self)?;
+        self.begin_variant(variant_index, variant)?;
         value.serialize(&mut *self)?;
-        Ok(())
+        self.end_list()
     }
 
     fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
-        // Before going deeper we have to introduce state to our serializer.
-        // This way we can capture output from a processed sequence.
-        // Once thats done, we can pop current state at the end of the sequence.
-        // We don't really care about the passed length as length is mostly unused,
-        // as sequences are converted to bytes first, and then the length is
-        // length of actual bytes of data.
-        self.buffer.push_front(self.output.clone());
-        self.output.clear();
+        // The list prefix holds the length in bytes, not in elements. It is
+        // taken from the measuring pass, so the element count is not needed.
+        self.begin_list()?;
         Ok(self)
     }
 
@@ -215,19 +455,18 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_tuple_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeTupleVariant> {
-        variant.serialize(&mut *self)?;
+        // Variant is a list with its tag followed by the fields
+        self.begin_variant(variant_index, variant)?;
         Ok(self)
     }
 
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
-        // Same as for sequences - we need to save current state of output,
-        // to be able to capture serialized values.
-        self.buffer.push_front(self.output.clone());
-        self.output.clear();
+        // Same as for sequences, the prefix comes from the measuring pass
+        self.begin_list()?;
         Ok(self)
     }
 
@@ -238,16 +477,19 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     fn serialize_struct_variant(
         self,
         _name: &'static str,
-        _variant_index: u32,
+        variant_index: u32,
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant> {
-        variant.serialize(&mut *self)?;
+        self.begin_variant(variant_index, variant)?;
         Ok(self)
     }
 }
 
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl<'a, W> ser::SerializeSeq for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
@@ -260,21 +502,15 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        // Calculate the serialization of the sequence based on the captured output.
-        // Note that this output is cleared out before returning SerializeSeq instance,
-        // and saved on a deque.
-        let mut prefix = rlp::encode_length(self.output.len() as u64, 0xc0);
-        prefix.extend(self.output.clone());
-        // This will get the current output, and after that pop the top of the buffer,
-        // which is the output *before* serializing the sequence.
-        self.output = self.buffer.pop_front().unwrap(); // This unwrap is safe assuming the normal path of the code.
-        self.output.extend(prefix);
-        Ok(())
+        self.end_list()
     }
 }
 
 // Same thing but for tuples.
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl<'a, W> ser::SerializeTuple for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
@@ -286,17 +522,15 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        let mut prefix = rlp::encode_length(self.output.len() as u64, 0xc0);
-        prefix.extend(self.output.clone());
-        // Restore original state after capturing this sequence
-        self.output = self.buffer.pop_front().unwrap();
-        self.output.extend(prefix);
-        Ok(())
+        self.end_list()
     }
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl<'a, W> ser::SerializeTupleStruct for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
@@ -308,11 +542,14 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_list()
     }
 }
 
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl<'a, W> ser::SerializeTupleVariant for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
@@ -324,18 +561,24 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_list()
     }
 }
 
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl<'a, W> ser::SerializeMap for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
+    // Every entry is a [key, value] pair, the same as structs with
+    // `StructEncoding::KeyValue`.
     fn serialize_key<T>(&mut self, key: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
+        self.begin_list()?;
         key.serialize(&mut **self)
     }
 
@@ -343,15 +586,19 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(&mut **self)
+        value.serialize(&mut **self)?;
+        self.end_list()
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_list()
     }
 }
 
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl<'a, W> ser::SerializeStruct for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
@@ -359,21 +606,18 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        // Serialize element of a structure as a sequence [key, value].
-        let dummy_seq = (&key, &value);
-        dummy_seq.serialize(&mut **self)
+        self.serialize_struct_field(key, value)
     }
 
     fn end(self) -> Result<()> {
-        let mut prefix = rlp::encode_length(self.output.len() as u64, 0xc0);
-        prefix.extend(self.output.clone());
-        self.output = self.buffer.pop_front().unwrap(); // This unwrap is safe assuming the normal path of the code.
-        self.output.extend(prefix);
-        Ok(())
+        self.end_struct()
     }
 }
 
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl<'a, W> ser::SerializeStructVariant for &mut Encoder<'a, W>
+where
+    W: io::Write,
+{
     type Ok = ();
     type Error = Error;
 
@@ -381,12 +625,12 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        key.serialize(&mut **self)?;
-        value.serialize(&mut **self)
+        // Fields follow the same layout as fields of a plain struct
+        self.serialize_struct_field(key, value)
     }
 
     fn end(self) -> Result<()> {
-        Ok(())
+        self.end_struct()
     }
 }
 
@@ -455,7 +699,8 @@ fn test_long_string() {
 
 #[test]
 fn test_integer_0() {
-    assert_eq!(to_bytes(&0u8).unwrap(), vec![0x00]);
+    assert_eq!(to_bytes(&0u8).unwrap(), vec![0x80]);
+    assert_eq!(to_bytes(&0u64).unwrap(), vec![0x80]);
 }
 
 #[test]
@@ -494,3 +739,270 @@ fn test_kv() {
         ]
     );
 }
+
+#[test]
+fn test_tuple_struct() {
+    #[derive(Serialize)]
+    struct Pair(u8, u8);
+    assert_eq!(to_bytes(&Pair(1, 2)).unwrap(), [0xc2, 0x01, 0x02]);
+    assert_eq!(
+        to_bytes(&vec![Pair(1, 2), Pair(3, 4)]).unwrap(),
+        [0xc6, 0xc2, 0x01, 0x02, 0xc2, 0x03, 0x04]
+    );
+}
+
+#[test]
+fn test_map() {
+    use std::collections::BTreeMap;
+    let mut map = BTreeMap::new();
+    map.insert("key1", "value1");
+    map.insert("key2", "value2");
+    // Same as [["key1", "value1"], ["key2", "value2"]]
+    assert_eq!(
+        to_bytes(&map).unwrap(),
+        to_bytes(&vec![vec!["key1", "value1"], vec!["key2", "value2"]]).unwrap()
+    );
+}
+
+#[test]
+fn test_tuple_variant() {
+    #[derive(Serialize)]
+    enum Shape {
+        Line(u8, u8),
+    }
+    // ["Line", 1, 2]
+    assert_eq!(
+        to_bytes(&vec![Shape::Line(1, 2)]).unwrap(),
+        [0xc8, 0xc7, 0x84, 0x4c, 0x69, 0x6e, 0x65, 0x01, 0x02]
+    );
+}
+
+#[test]
+fn test_struct_variant() {
+    #[derive(Serialize)]
+    enum Shape {
+        Point { x: u8, y: u8 },
+    }
+    // ["Point", 1, 2]
+    assert_eq!(
+        to_bytes(&Shape::Point { x: 1, y: 2 }).unwrap(),
+        [0xc8, 0x85, 0x50, 0x6f, 0x69, 0x6e, 0x74, 0x01, 0x02]
+    );
+}
+
+#[test]
+fn test_unit_and_newtype_variants() {
+    #[derive(Serialize)]
+    enum Message {
+        Ping,
+        Nonce(u8),
+    }
+    // ["Ping"]
+    assert_eq!(
+        to_bytes(&Message::Ping).unwrap(),
+        [0xc5, 0x84, 0x50, 0x69, 0x6e, 0x67]
+    );
+    // ["Nonce", 5]
+    assert_eq!(
+        to_bytes(&Message::Nonce(5)).unwrap(),
+        [0xc7, 0x85, 0x4e, 0x6f, 0x6e, 0x63, 0x65, 0x05]
+    );
+}
+
+#[test]
+fn test_variants_tagged_by_index() {
+    #[derive(Serialize)]
+    enum Message {
+        Ping,
+        Nonce(u8),
+        Line(u8, u8),
+        Point { x: u8, y: u8 },
+    }
+    let serializer = Serializer::new().variant_tag(VariantTag::Index);
+    // [0]
+    assert_eq!(serializer.to_bytes(&Message::Ping).unwrap(), [0xc1, 0x80]);
+    // [1, 5]
+    assert_eq!(
+        serializer.to_bytes(&Message::Nonce(5)).unwrap(),
+        [0xc2, 0x01, 0x05]
+    );
+    // [2, 1, 2]
+    assert_eq!(
+        serializer.to_bytes(&Message::Line(1, 2)).unwrap(),
+        [0xc3, 0x02, 0x01, 0x02]
+    );
+    // [3, 1, 2]
+    assert_eq!(
+        serializer.to_bytes(&Message::Point { x: 1, y: 2 }).unwrap(),
+        [0xc3, 0x03, 0x01, 0x02]
+    );
+}
+
+#[test]
+fn test_to_writer() {
+    let data = vec![vec!["cat", "dog"], vec!["cat", "dog"]];
+    let mut output = Vec::new();
+    to_writer(&mut output, &data).unwrap();
+    assert_eq!(output, to_bytes(&data).unwrap());
+}
+
+#[test]
+fn test_to_writer_nested_long_lists() {
+    // Lists longer than 55 bytes need a multi byte prefix
+    let data = vec![vec!["Lorem ipsum dolor sit amet"; 3]; 4];
+    let mut output = Vec::new();
+    to_writer(&mut output, &data).unwrap();
+    assert_eq!(
+        &output[..9],
+        &[0xf9, 0x01, 0x4c, 0xf8, 0x51, 0x9a, 0x4c, 0x6f, 0x72][..]
+    );
+    assert_eq!(output.len(), 335);
+}
+
+#[test]
+fn test_to_writer_error() {
+    struct FailingWriter;
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    match to_writer(FailingWriter, &vec!["cat", "dog"])
+        .unwrap_err()
+        .into_kind()
+    {
+        ErrorKind::Io(e) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+        e => panic!("Unexpected error {:?}", e),
+    }
+}
+
+#[test]
+fn test_unsupported_types() {
+    #[derive(Serialize)]
+    struct Unit;
+    assert_eq!(
+        to_bytes(&-1i8),
+        Err(ErrorKind::UnsupportedType("signed integer").into())
+    );
+    assert_eq!(
+        to_bytes(&1i64),
+        Err(ErrorKind::UnsupportedType("signed integer").into())
+    );
+    assert_eq!(
+        to_bytes(&1f32),
+        Err(ErrorKind::UnsupportedType("f32").into())
+    );
+    assert_eq!(
+        to_bytes(&1f64),
+        Err(ErrorKind::UnsupportedType("f64").into())
+    );
+    assert_eq!(
+        to_bytes(&()),
+        Err(ErrorKind::UnsupportedType("unit").into())
+    );
+    assert_eq!(
+        to_bytes(&Unit),
+        Err(ErrorKind::UnsupportedType("unit struct").into())
+    );
+    // Nested values fail the same way
+    assert_eq!(
+        to_bytes(&(1u8, vec![1.0f32])),
+        Err(ErrorKind::UnsupportedType("f32").into())
+    );
+}
+
+#[test]
+fn test_signed_integers() {
+    let zigzag = Serializer::new().signed_encoding(SignedEncoding::ZigZag);
+    assert_eq!(zigzag.to_bytes(&0i8).unwrap(), [0x80]);
+    assert_eq!(zigzag.to_bytes(&-1i16).unwrap(), [0x01]);
+    assert_eq!(zigzag.to_bytes(&-65i32).unwrap(), [0x81, 0x81]);
+    assert_eq!(zigzag.to_bytes(&1000i64).unwrap(), [0x82, 0x07, 0xd0]);
+
+    let twos = Serializer::new().signed_encoding(SignedEncoding::TwosComplement);
+    assert_eq!(twos.to_bytes(&0i8).unwrap(), [0x80]);
+    assert_eq!(twos.to_bytes(&-1i16).unwrap(), [0x81, 0xff]);
+    assert_eq!(twos.to_bytes(&127i32).unwrap(), [0x7f]);
+    assert_eq!(twos.to_bytes(&128i64).unwrap(), [0x82, 0x00, 0x80]);
+
+    let mut expected = vec![0x90, 0x80];
+    expected.extend_from_slice(&[0x00; 15]);
+    assert_eq!(twos.to_bytes(&i128::MIN).unwrap(), expected);
+    assert_eq!(zigzag.to_bytes(&-1i128).unwrap(), [0x01]);
+    assert_eq!(
+        to_bytes(&1i128),
+        Err(ErrorKind::UnsupportedType("signed integer").into())
+    );
+}
+
+#[test]
+fn test_u128() {
+    let mut expected = vec![0x90];
+    expected.extend_from_slice(&[0xff; 16]);
+    assert_eq!(to_bytes(&u128::MAX).unwrap(), expected);
+    assert_eq!(to_bytes(&1024u128).unwrap(), [0x82, 0x04, 0x00]);
+}
+
+#[test]
+fn test_bool() {
+    assert_eq!(to_bytes(&true).unwrap(), [0x01]);
+    assert_eq!(to_bytes(&false).unwrap(), [0x80]);
+    assert_eq!(to_bytes(&(true, false)).unwrap(), [0xc2, 0x01, 0x80]);
+}
+
+#[test]
+fn test_options() {
+    #[derive(Serialize)]
+    struct Header {
+        number: u64,
+        base_fee: Option<u64>,
+        withdrawals: Option<u64>,
+    }
+    let header = |base_fee, withdrawals| Header {
+        number: 1,
+        base_fee,
+        withdrawals,
+    };
+
+    let empty_string = Serializer::new();
+    assert_eq!(empty_string.to_bytes(&None::<u64>).unwrap(), [0x80]);
+    assert_eq!(empty_string.to_bytes(&Some(15u64)).unwrap(), [0x0f]);
+    assert_eq!(
+        empty_string.to_bytes(&header(None, Some(7))).unwrap(),
+        [0xc3, 0x01, 0x80, 0x07]
+    );
+
+    let list = Serializer::new().option_encoding(OptionEncoding::List);
+    assert_eq!(list.to_bytes(&None::<u64>).unwrap(), [0xc0]);
+    assert_eq!(list.to_bytes(&Some(0u64)).unwrap(), [0xc1, 0x80]);
+    assert_eq!(
+        list.to_bytes(&header(None, Some(7))).unwrap(),
+        [0xc4, 0x01, 0xc0, 0xc1, 0x07]
+    );
+
+    let trailing = Serializer::new().option_encoding(OptionEncoding::Trailing);
+    assert_eq!(trailing.to_bytes(&None::<u64>).unwrap(), [0x80]);
+    assert_eq!(
+        trailing.to_bytes(&header(None, None)).unwrap(),
+        [0xc1, 0x01]
+    );
+    assert_eq!(
+        trailing.to_bytes(&header(Some(7), None)).unwrap(),
+        [0xc2, 0x01, 0x07]
+    );
+    assert_eq!(
+        trailing.to_bytes(&header(None, Some(7))).unwrap(),
+        [0xc3, 0x01, 0x80, 0x07]
+    );
+    // Only struct fields can be left out
+    assert_eq!(
+        trailing.to_bytes(&vec![Some(1u8), None]).unwrap(),
+        [0xc2, 0x01, 0x80]
+    );
+}
@@ -0,0 +1,145 @@
+// Copyright 2018 Althea Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! 256-bit unsigned integers, like Ethereum balances and difficulties, stored
+//! in `num::BigUint`. Use it on a field with `#[serde(with = "serde_rlp::u256")]`.
+//!
+//! Values are encoded as minimal big-endian bytes, the same way as the builtin
+//! integer types. Wider values and leading zero bytes are rejected. The
+//! functions here work with any serde deserializer and can not see its
+//! settings, so leading zeros are rejected even with `strict(false)`.
+
+use std::fmt;
+
+use num::BigUint;
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use serde::ser::{self, Serializer};
+
+/// Largest encoded size, in bytes
+const MAX_BYTES: usize = 32;
+
+pub fn serialize<S>(value: &BigUint, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if value.bits() > MAX_BYTES * 8 {
+        return Err(ser::Error::custom("integer is wider than 256 bits"));
+    }
+    let bytes = value.to_bytes_be();
+    // Zero comes out as a single zero byte, but it's the empty string in RLP
+    if bytes == [0] {
+        serializer.serialize_bytes(&[])
+    } else {
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<BigUint, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserializer.deserialize_bytes(U256Visitor)
+}
+
+struct U256Visitor;
+
+impl<'de> Visitor<'de> for U256Visitor {
+    type Value = BigUint;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an unsigned integer of at most 256 bits")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<BigUint, E> {
+        if v.len() > MAX_BYTES {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        if v.first() == Some(&0) {
+            return Err(E::invalid_value(
+                Unexpected::Bytes(v),
+                &"an integer without leading zero bytes",
+            ));
+        }
+        Ok(BigUint::from_bytes_be(v))
+    }
+}
+
+#[cfg(test)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Account {
+    nonce: u64,
+    #[serde(with = "::u256")]
+    balance: BigUint,
+}
+
+#[test]
+fn u256_roundtrip() {
+    use de::from_bytes;
+    use ser::to_bytes;
+
+    let max = BigUint::from_bytes_be(&[0xff; 32]);
+    for balance in [BigUint::from(0u8), BigUint::from(1024u16), max] {
+        let account = Account { nonce: 1, balance };
+        let data = to_bytes(&account).unwrap();
+        assert_eq!(from_bytes::<Account>(&data).unwrap(), account);
+    }
+}
+
+#[test]
+fn u256_encoding() {
+    use de::from_bytes;
+    use ser::to_bytes;
+
+    let account = Account {
+        nonce: 0,
+        balance: BigUint::from(0u8),
+    };
+    assert_eq!(to_bytes(&account).unwrap(), [0xc2, 0x80, 0x80]);
+    let account = Account {
+        nonce: 0,
+        balance: BigUint::from(1_000_000_000_000_000_000u64),
+    };
+    let data = to_bytes(&account).unwrap();
+    assert_eq!(
+        data,
+        [0xca, 0x80, 0x88, 0x0d, 0xe0, 0xb6, 0xb3, 0xa7, 0x64, 0x00, 0x00]
+    );
+    assert_eq!(from_bytes::<Account>(&data).unwrap(), account);
+}
+
+#[test]
+fn u256_out_of_range() {
+    use de::from_bytes;
+    use ser::to_bytes;
+
+    let account = Account {
+        nonce: 0,
+        balance: BigUint::from_bytes_be(&[0x01; 33]),
+    };
+    assert!(to_bytes(&account).is_err());
+
+    let mut data = vec![0xf8, 0x23, 0x80, 0xa1];
+    data.extend_from_slice(&[0x01; 33]);
+    assert!(from_bytes::<Account>(&data).is_err());
+    // Leading zero byte
+    assert!(from_bytes::<Account>(&[0xc4, 0x80, 0x82, 0x00, 0x01]).is_err());
+}
+
+#[test]
+fn u256_leading_zeros_ignore_strictness() {
+    use de::{DecodeOptions, Deserializer};
+    use serde::Deserialize;
+
+    let data = [0xc4, 0x80, 0x82, 0x00, 0x01];
+    let mut deserializer = Deserializer::with_options(&data, DecodeOptions::new().strict(false));
+    assert!(Account::deserialize(&mut deserializer).is_err());
+    // Builtin integers do accept them
+    let mut deserializer =
+        Deserializer::with_options(&data[2..], DecodeOptions::new().strict(false));
+    assert_eq!(u64::deserialize(&mut deserializer).unwrap(), 1);
+}
@@ -0,0 +1,237 @@
+// Copyright 2018 Althea Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Schema-less representation of RLP data, for inspecting unknown blobs.
+
+use std::fmt;
+use std::ops::Index;
+use std::str;
+
+use serde::de::{self, DeserializeOwned, Deserializer, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+
+use de::from_bytes;
+use error::Result;
+use rlp;
+use ser::to_bytes;
+
+/// Any RLP item: either a byte string or a list of items.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RlpValue {
+    Bytes(Vec<u8>),
+    List(Vec<RlpValue>),
+}
+
+impl RlpValue {
+    pub fn is_bytes(&self) -> bool {
+        self.as_bytes().is_some()
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.as_list().is_some()
+    }
+
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match *self {
+            RlpValue::Bytes(ref bytes) => Some(bytes),
+            RlpValue::List(_) => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[RlpValue]> {
+        match *self {
+            RlpValue::Bytes(_) => None,
+            RlpValue::List(ref items) => Some(items),
+        }
+    }
+
+    /// Returns the string as UTF-8 text, if it is valid.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_bytes().and_then(|bytes| str::from_utf8(bytes).ok())
+    }
+
+    /// Returns the string as a big-endian integer, if it fits into `u64`.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_bytes()
+            .and_then(|bytes| rlp::decode_number(bytes).ok())
+    }
+
+    /// Returns the item at `index` of a list. `None` for strings and for
+    /// indices out of bounds.
+    pub fn get(&self, index: usize) -> Option<&RlpValue> {
+        self.as_list().and_then(|items| items.get(index))
+    }
+}
+
+/// Indexes into a list. Panics if the value is a string or the index is out
+/// of bounds, use `get` to avoid that.
+impl Index<usize> for RlpValue {
+    type Output = RlpValue;
+
+    fn index(&self, index: usize) -> &RlpValue {
+        match *self {
+            RlpValue::Bytes(_) => panic!("cannot index into an RLP string"),
+            RlpValue::List(ref items) => &items[index],
+        }
+    }
+}
+
+impl From<Vec<u8>> for RlpValue {
+    fn from(bytes: Vec<u8>) -> Self {
+        RlpValue::Bytes(bytes)
+    }
+}
+
+impl<'a> From<&'a [u8]> for RlpValue {
+    fn from(bytes: &'a [u8]) -> Self {
+        RlpValue::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for RlpValue {
+    fn from(s: String) -> Self {
+        RlpValue::Bytes(s.into_bytes())
+    }
+}
+
+impl<'a> From<&'a str> for RlpValue {
+    fn from(s: &'a str) -> Self {
+        RlpValue::Bytes(s.as_bytes().to_vec())
+    }
+}
+
+impl From<u64> for RlpValue {
+    fn from(v: u64) -> Self {
+        RlpValue::Bytes(rlp::encode_number(v).to_vec())
+    }
+}
+
+impl From<Vec<RlpValue>> for RlpValue {
+    fn from(items: Vec<RlpValue>) -> Self {
+        RlpValue::List(items)
+    }
+}
+
+impl Serialize for RlpValue {
+    fn serialize<S>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match *self {
+            RlpValue::Bytes(ref bytes) => serializer.serialize_bytes(bytes),
+            RlpValue::List(ref items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+struct RlpValueVisitor;
+
+impl<'de> Visitor<'de> for RlpValueVisitor {
+    type Value = RlpValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an RLP string or list")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> ::std::result::Result<RlpValue, E> {
+        Ok(RlpValue::Bytes(v.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> ::std::result::Result<RlpValue, E> {
+        Ok(RlpValue::Bytes(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> ::std::result::Result<RlpValue, E> {
+        Ok(RlpValue::from(v))
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> ::std::result::Result<RlpValue, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(RlpValue::List(items))
+    }
+}
+
+impl<'de> Deserialize<'de> for RlpValue {
+    fn deserialize<D>(deserializer: D) -> ::std::result::Result<RlpValue, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(RlpValueVisitor)
+    }
+}
+
+/// Converts any serializable value into its RLP tree.
+pub fn to_value<T>(value: &T) -> Result<RlpValue>
+where
+    T: Serialize,
+{
+    from_bytes(&to_bytes(value)?)
+}
+
+/// Interprets an RLP tree as a value of type `T`.
+pub fn from_value<T>(value: &RlpValue) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_bytes(&to_bytes(value)?)
+}
+
+#[test]
+fn decode_nested_value() {
+    // [ [], [[]], [ [], [[]] ] ]
+    let value: RlpValue = from_bytes(&[0xc7, 0xc0, 0xc1, 0xc0, 0xc3, 0xc0, 0xc1, 0xc0]).unwrap();
+    let empty = RlpValue::List(vec![]);
+    assert_eq!(value[0], empty);
+    assert_eq!(value[1][0], empty);
+    assert_eq!(value[2][1][0], empty);
+    assert_eq!(value.get(3), None);
+    assert_eq!(
+        to_bytes(&value).unwrap(),
+        [0xc7, 0xc0, 0xc1, 0xc0, 0xc3, 0xc0, 0xc1, 0xc0]
+    );
+}
+
+#[test]
+fn value_conversions() {
+    let value = to_value(&("cat", 1024u64, vec!["dog"])).unwrap();
+    assert!(value.is_list());
+    assert_eq!(value[0].as_str(), Some("cat"));
+    assert_eq!(value[1].as_u64(), Some(1024));
+    assert_eq!(value[1].as_bytes(), Some(&[0x04, 0x00][..]));
+    assert_eq!(value[2][0], RlpValue::from("dog"));
+    assert_eq!(value.as_str(), None);
+    assert_eq!(RlpValue::from(vec![0u8; 9]).as_u64(), None);
+
+    let back: (String, u64, Vec<String>) = from_value(&value).unwrap();
+    assert_eq!(back, ("cat".to_string(), 1024, vec!["dog".to_string()]));
+}
+
+#[test]
+fn build_value() {
+    let value = RlpValue::from(vec![RlpValue::from(0u64), RlpValue::from(15u64)]);
+    assert_eq!(to_bytes(&value).unwrap(), [0xc2, 0x80, 0x0f]);
+}
+
+#[test]
+#[should_panic]
+fn index_into_string() {
+    let _ = RlpValue::from("cat")[0];
+}
@@ -0,0 +1,269 @@
+// Copyright 2018 Althea Developers
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lazy view into encoded data, for picking a few items out of a large blob
+//! without decoding all of it.
+
+use serde::Deserialize;
+
+use de::{from_bytes, from_bytes_with, DecodeOptions};
+use error::{ErrorKind, Result};
+use rlp::{self, ExpectedType};
+
+/// Borrowed RLP item. Nested items are found by walking their headers, nothing
+/// is copied or decoded until `as_val` is called.
+///
+/// Navigation only checks that items fit into the data. Canonical encoding is
+/// checked by `as_val`, like it is by `from_bytes`, unless `as_val_with` is
+/// given other settings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rlp<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Rlp<'a> {
+    /// View of the item at the start of `bytes`. Anything after that item is
+    /// left out of the view.
+    pub fn new(bytes: &'a [u8]) -> Self {
+        match rlp::decode_length(bytes) {
+            Ok(res) => Rlp {
+                bytes: &bytes[..res.offset + res.length],
+            },
+            // Broken headers are reported when the item is used
+            Err(_) => Rlp { bytes },
+        }
+    }
+
+    /// Encoded item, header included.
+    pub fn as_raw(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn is_list(&self) -> bool {
+        self.bytes.first().is_some_and(|&prefix| prefix >= 0xc0)
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.bytes.first().is_some_and(|&prefix| prefix < 0xc0)
+    }
+
+    /// Payload of a string item.
+    pub fn data(&self) -> Result<&'a [u8]> {
+        match self.payload()? {
+            (ExpectedType::StringType, payload) => Ok(payload),
+            (ExpectedType::ListType, _) => Err(ErrorKind::ExpectedString.into()),
+        }
+    }
+
+    /// Number of items in a list.
+    pub fn item_count(&self) -> Result<usize> {
+        self.iter()
+            .try_fold(0, |count, item| item.map(|_| count + 1))
+    }
+
+    /// Item at `index` of a list.
+    pub fn at(&self, index: usize) -> Result<Rlp<'a>> {
+        match self.iter().nth(index) {
+            Some(item) => item,
+            None => Err(ErrorKind::IndexOutOfBounds {
+                index,
+                count: self.item_count()?,
+            }
+            .into()),
+        }
+    }
+
+    /// Iterates over the items of a list. Iteration ends after the first error.
+    pub fn iter(&self) -> RlpIter<'a> {
+        let items = match self.payload() {
+            Ok((ExpectedType::ListType, payload)) => Ok(payload),
+            Ok((ExpectedType::StringType, _)) => Err(ErrorKind::ExpectedList.into()),
+            Err(e) => Err(e),
+        };
+        RlpIter { items }
+    }
+
+    /// Deserializes the item. Borrowed types like `&str` point into the
+    /// original data.
+    pub fn as_val<T>(&self) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        from_bytes(self.bytes)
+    }
+
+    /// Like `as_val`, with the given settings.
+    pub fn as_val_with<T>(&self, options: DecodeOptions) -> Result<T>
+    where
+        T: Deserialize<'a>,
+    {
+        from_bytes_with(self.bytes, options)
+    }
+
+    fn payload(&self) -> Result<(ExpectedType, &'a [u8])> {
+        let res = rlp::decode_length(self.bytes)?;
+        Ok((
+            res.expected_type,
+            &self.bytes[res.offset..res.offset + res.length],
+        ))
+    }
+}
+
+impl<'a> IntoIterator for Rlp<'a> {
+    type Item = Result<Rlp<'a>>;
+    type IntoIter = RlpIter<'a>;
+
+    fn into_iter(self) -> RlpIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the items of a list, see `Rlp::iter`.
+pub struct RlpIter<'a> {
+    /// Items not visited yet, or why the list can't be walked
+    items: Result<&'a [u8]>,
+}
+
+impl<'a> Iterator for RlpIter<'a> {
+    type Item = Result<Rlp<'a>>;
+
+    fn next(&mut self) -> Option<Result<Rlp<'a>>> {
+        let rest = match self.items {
+            Ok(rest) => rest,
+            Err(ref e) => {
+                let e = e.clone();
+                self.items = Ok(&[]);
+                return Some(Err(e));
+            }
+        };
+        if rest.is_empty() {
+            return None;
+        }
+        match rlp::decode_length(rest) {
+            Ok(res) => {
+                let end = res.offset + res.length;
+                self.items = Ok(&rest[end..]);
+                Some(Ok(Rlp::new(&rest[..end])))
+            }
+            Err(e) => {
+                self.items = Ok(&[]);
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[test]
+fn navigate_nested_items() {
+    use ser::to_bytes;
+
+    let data = to_bytes(&(1024u64, ("cat", vec!["dog", "ox"]), "")).unwrap();
+    let rlp = Rlp::new(&data);
+    assert!(rlp.is_list());
+    assert_eq!(rlp.item_count().unwrap(), 3);
+    assert_eq!(rlp.at(0).unwrap().as_val::<u64>().unwrap(), 1024);
+    assert_eq!(rlp.at(0).unwrap().data().unwrap(), [0x04, 0x00]);
+    assert_eq!(rlp.at(0).unwrap().as_raw(), [0x82, 0x04, 0x00]);
+    assert_eq!(rlp.at(1).unwrap().at(0).unwrap().data().unwrap(), b"cat");
+    let animals = rlp.at(1).unwrap().at(1).unwrap();
+    let names: Vec<&str> = animals
+        .iter()
+        .map(|item| item.unwrap().as_val().unwrap())
+        .collect();
+    assert_eq!(names, ["dog", "ox"]);
+    assert_eq!(animals.as_val::<Vec<String>>().unwrap(), ["dog", "ox"]);
+    assert!(rlp.at(2).unwrap().is_data());
+    assert_eq!(rlp.at(2).unwrap().data().unwrap(), []);
+}
+
+#[test]
+fn view_borrows_data() {
+    let data = [0xc5, 0x83, b'c', b'a', b't', 0xc0];
+    let rlp = Rlp::new(&data);
+    let cat: &str = rlp.at(0).unwrap().as_val().unwrap();
+    assert_eq!(cat.as_ptr(), data[2..].as_ptr());
+    assert_eq!(rlp.at(1).unwrap().as_raw().as_ptr(), data[5..].as_ptr());
+}
+
+#[test]
+fn view_ignores_trailing_bytes() {
+    let rlp = Rlp::new(&[0x01, 0x02]);
+    assert_eq!(rlp.as_raw(), [0x01]);
+    assert_eq!(rlp.data().unwrap(), [0x01]);
+    assert_eq!(rlp.as_val::<u8>().unwrap(), 1);
+
+    let rlp = Rlp::new(&[0xc1, 0x01, 0xc0]);
+    assert_eq!(rlp.as_raw(), [0xc1, 0x01]);
+    assert_eq!(rlp.item_count().unwrap(), 1);
+    assert_eq!(rlp.as_val::<Vec<u8>>().unwrap(), [1]);
+}
+
+#[test]
+fn view_decodes_with_options() {
+    use config::OptionEncoding;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Header {
+        number: u64,
+        base_fee: Option<u64>,
+    }
+
+    // [[0x0001], [7]]
+    let data = [0xc6, 0xc3, 0x82, 0x00, 0x01, 0xc1, 0x07];
+    let rlp = Rlp::new(&data);
+    assert!(rlp.at(0).unwrap().as_val::<(u64,)>().is_err());
+    let lenient = DecodeOptions::new().strict(false);
+    assert_eq!(
+        rlp.at(0).unwrap().as_val_with::<(u64,)>(lenient).unwrap(),
+        (1,)
+    );
+    let trailing = DecodeOptions::new().option_encoding(OptionEncoding::Trailing);
+    assert_eq!(
+        rlp.at(1).unwrap().as_val_with::<Header>(trailing).unwrap(),
+        Header {
+            number: 7,
+            base_fee: None
+        }
+    );
+}
+
+#[test]
+fn view_errors() {
+    let rlp = Rlp::new(&[0xc2, 0x01, 0x02]);
+    assert_eq!(
+        rlp.at(2).unwrap_err().into_kind(),
+        ErrorKind::IndexOutOfBounds { index: 2, count: 2 }
+    );
+    assert_eq!(
+        rlp.data().unwrap_err().into_kind(),
+        ErrorKind::ExpectedString
+    );
+    assert_eq!(
+        rlp.at(0).unwrap().item_count().unwrap_err().into_kind(),
+        ErrorKind::ExpectedList
+    );
+    assert!(!Rlp::new(&[]).is_list());
+    assert_eq!(
+        Rlp::new(&[]).data().unwrap_err().into_kind(),
+        ErrorKind::EmptyBuffer
+    );
+
+    // The second item claims more bytes than the list holds
+    let rlp = Rlp::new(&[0xc3, 0x01, 0x83, 0x61]);
+    let mut items = rlp.iter();
+    assert_eq!(items.next().unwrap().unwrap().as_raw(), [0x01]);
+    assert_eq!(
+        items.next().unwrap().unwrap_err().into_kind(),
+        ErrorKind::StringPrefixTooSmall
+    );
+    assert!(items.next().is_none());
+    assert_eq!(
+        rlp.at(1).unwrap_err().into_kind(),
+        ErrorKind::StringPrefixTooSmall
+    );
+}
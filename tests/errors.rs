@@ -0,0 +1,128 @@
+extern crate num;
+extern crate serde_rlp;
+#[macro_use]
+extern crate serde_derive;
+
+use std::error::Error as StdError;
+use std::io;
+
+use num::BigUint;
+use serde_rlp::config::VariantTag;
+use serde_rlp::de::{from_bytes, from_bytes_with, from_reader, DecodeOptions};
+use serde_rlp::{ErrorKind, NonCanonical, Result};
+
+#[test]
+fn errors_can_be_matched() {
+    let res: Result<u8> = from_bytes(&[0x82, 0x01, 0x00]);
+    let err = res.unwrap_err();
+    match *err.kind() {
+        ErrorKind::IntegerOverflow { target } => assert_eq!(target, "u8"),
+        ref other => panic!("Unexpected error {:?}", other),
+    }
+
+    let err = from_bytes::<u64>(&[0x82, 0x00, 0x01]).unwrap_err();
+    assert_eq!(
+        err.into_kind(),
+        ErrorKind::NonCanonical(NonCanonical::IntegerPadding)
+    );
+
+    let err = from_reader::<_, String>(&[0x83, b'c'][..]).unwrap_err();
+    assert_eq!(err.offset(), Some(0));
+    assert_eq!(err.into_kind(), ErrorKind::UnexpectedEof { missing: 2 });
+}
+
+#[test]
+fn errors_tell_where_they_happened() {
+    // [1, [2, 256]] decoded as (u8, Vec<u8>)
+    let data = [0xc6, 0x01, 0xc4, 0x02, 0x82, 0x01, 0x00];
+    let err = from_bytes::<(u8, Vec<u8>)>(&data).unwrap_err();
+    assert_eq!(*err.kind(), ErrorKind::IntegerOverflow { target: "u8" });
+    assert_eq!(err.offset(), Some(4));
+    assert_eq!(err.path(), Some("[1][1]"));
+}
+
+#[test]
+fn errors_display_their_data() {
+    let err = from_bytes::<u16>(&[0x83, 0x01, 0x00, 0x00]).unwrap_err();
+    assert_eq!(err.to_string(), "Integer is too big for u16 at offset 0");
+    assert_eq!(err.offset(), Some(0));
+    assert!(err.source().is_none());
+    assert_eq!(
+        ErrorKind::NonCanonical(NonCanonical::LongForm).to_string(),
+        "Non-canonical encoding, long form length is used for a short item"
+    );
+    assert_eq!(
+        ErrorKind::UnsupportedType("f32").to_string(),
+        "Type f32 can not be represented in RLP"
+    );
+}
+
+#[test]
+fn io_errors_keep_their_source() {
+    struct Broken;
+
+    impl io::Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::ConnectionReset, "peer left"))
+        }
+    }
+
+    let err = from_reader::<_, u8>(Broken).unwrap_err();
+    match *err.kind() {
+        ErrorKind::Io(ref io_error) => assert_eq!(io_error.kind(), io::ErrorKind::ConnectionReset),
+        ref other => panic!("Unexpected error {:?}", other),
+    }
+    assert_eq!(err.to_string(), "I/O error: peer left at offset 0");
+    assert_eq!(err.source().unwrap().to_string(), "peer left");
+}
+
+// Items rejected by the visitor are reported at their start, not past them
+
+#[test]
+fn fixed_bytes_errors_point_at_the_item() {
+    #[derive(Debug, Deserialize)]
+    struct Log {
+        #[serde(with = "serde_rlp::fixed_bytes")]
+        _address: [u8; 2],
+    }
+
+    let err = from_bytes::<Log>(&[0xc4, 0x83, 0x11, 0x22, 0x33]).unwrap_err();
+    assert_eq!(err.offset(), Some(1));
+    assert_eq!(err.path(), Some("_address"));
+}
+
+#[test]
+fn u256_errors_point_at_the_item() {
+    #[derive(Debug, Deserialize)]
+    struct Account {
+        _nonce: u64,
+        #[serde(with = "serde_rlp::u256")]
+        _balance: BigUint,
+    }
+
+    let err = from_bytes::<Account>(&[0xc4, 0x80, 0x82, 0x00, 0x01]).unwrap_err();
+    assert_eq!(err.offset(), Some(2));
+    assert_eq!(err.path(), Some("_balance"));
+}
+
+#[test]
+fn char_errors_point_at_the_item() {
+    let err = from_bytes::<(u8, char)>(&[0xc4, 0x01, 0x82, b'a', b'b']).unwrap_err();
+    assert_eq!(err.offset(), Some(2));
+    assert_eq!(err.path(), Some("[1]"));
+}
+
+#[test]
+fn unknown_variant_errors_point_at_the_tag() {
+    #[derive(Debug, Deserialize)]
+    enum Message {
+        Ping,
+    }
+
+    let err = from_bytes::<Message>(&[0xc5, 0x84, b'P', b'o', b'n', b'g']).unwrap_err();
+    assert_eq!(err.offset(), Some(1));
+
+    let by_index = DecodeOptions::new().variant_tag(VariantTag::Index);
+    let err = from_bytes_with::<Message>(&[0xc1, 0x05], by_index).unwrap_err();
+    assert_eq!(err.offset(), Some(1));
+}
@@ -0,0 +1,97 @@
+extern crate num;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate serde_rlp;
+
+use num::{BigUint, ToPrimitive};
+use serde_json::{Map, Value as Json};
+use serde_rlp::de::from_bytes;
+use serde_rlp::ser::to_bytes;
+use serde_rlp::value::RlpValue;
+
+fn load(json: &str) -> Map<String, Json> {
+    serde_json::from_str(json).expect("Unable to parse test vectors")
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    let s = s.trim_start_matches("0x");
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// Big integers are written as `#` followed by decimal digits.
+fn big_integer(s: &str) -> Option<BigUint> {
+    s.strip_prefix('#')
+        .map(|digits| BigUint::parse_bytes(digits.as_bytes(), 10).expect("Invalid big integer"))
+}
+
+/// Converts the input of a valid test into the tree it encodes.
+fn to_rlp_value(json: &Json) -> RlpValue {
+    match *json {
+        Json::String(ref s) => match big_integer(s) {
+            Some(n) => RlpValue::Bytes(n.to_bytes_be()),
+            None => RlpValue::from(s.as_str()),
+        },
+        Json::Number(ref n) => RlpValue::from(n.as_u64().unwrap()),
+        Json::Array(ref items) => RlpValue::List(items.iter().map(to_rlp_value).collect()),
+        _ => panic!("Unexpected test input {}", json),
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct U256(#[serde(with = "serde_rlp::u256")] BigUint);
+
+/// Encodes and decodes a big integer with every type wide enough to hold it.
+fn check_big_integer(name: &str, n: BigUint, out: &[u8]) {
+    if let Some(n) = n.to_u128() {
+        assert_eq!(to_bytes(&n).unwrap(), out, "{}", name);
+        assert_eq!(from_bytes::<u128>(out).unwrap(), n, "{}", name);
+    }
+    if n.bits() <= 256 {
+        let n = U256(n);
+        assert_eq!(to_bytes(&n).unwrap(), out, "{}", name);
+        assert_eq!(from_bytes::<U256>(out).unwrap(), n, "{}", name);
+    } else {
+        assert!(to_bytes(&U256(n)).is_err(), "{}", name);
+        assert!(from_bytes::<U256>(out).is_err(), "{}", name);
+    }
+}
+
+#[test]
+fn valid_vectors() {
+    for (name, test) in load(include_str!("RLPTests/rlptest.json")) {
+        let input = &test["in"];
+        let out = hex(test["out"].as_str().unwrap());
+        let value = to_rlp_value(input);
+        assert_eq!(to_bytes(&value).unwrap(), out, "{}", name);
+        assert_eq!(from_bytes::<RlpValue>(&out).unwrap(), value, "{}", name);
+
+        match *input {
+            Json::String(ref s) => match big_integer(s) {
+                Some(n) => check_big_integer(&name, n, &out),
+                None => {
+                    assert_eq!(to_bytes(&s).unwrap(), out, "{}", name);
+                    assert_eq!(&from_bytes::<String>(&out).unwrap(), s, "{}", name);
+                }
+            },
+            Json::Number(ref n) => {
+                let n = n.as_u64().unwrap();
+                assert_eq!(to_bytes(&n).unwrap(), out, "{}", name);
+                assert_eq!(from_bytes::<u64>(&out).unwrap(), n, "{}", name);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn invalid_vectors() {
+    for (name, test) in load(include_str!("RLPTests/invalidRLPTest.json")) {
+        let out = hex(test["out"].as_str().unwrap());
+        assert!(from_bytes::<RlpValue>(&out).is_err(), "{}", name);
+    }
+}
@@ -0,0 +1,127 @@
+extern crate serde;
+extern crate serde_rlp;
+#[macro_use]
+extern crate serde_derive;
+
+use serde::{Deserialize, Serialize};
+use serde_rlp::config::{StructEncoding, VariantTag};
+use serde_rlp::de::{from_bytes, DecodeOptions, Deserializer};
+use serde_rlp::ser::{to_bytes, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+fn roundtrip<T>(value: &T)
+where
+    T: Debug + PartialEq + Serialize + for<'de> Deserialize<'de>,
+{
+    let bytes = to_bytes(value).unwrap();
+    assert_eq!(&from_bytes::<T>(&bytes).unwrap(), value);
+
+    let bytes = Serializer::new()
+        .struct_encoding(StructEncoding::KeyValue)
+        .to_bytes(value)
+        .unwrap();
+    let mut deserializer = Deserializer::with_options(
+        &bytes,
+        DecodeOptions::new().struct_encoding(StructEncoding::KeyValue),
+    );
+    assert_eq!(&T::deserialize(&mut deserializer).unwrap(), value);
+    deserializer.end().unwrap();
+
+    let bytes = Serializer::new()
+        .variant_tag(VariantTag::Index)
+        .to_bytes(value)
+        .unwrap();
+    let mut deserializer =
+        Deserializer::with_options(&bytes, DecodeOptions::new().variant_tag(VariantTag::Index));
+    assert_eq!(&T::deserialize(&mut deserializer).unwrap(), value);
+    deserializer.end().unwrap();
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Nonce(u64);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Signature(u64, String, String);
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Message {
+    Ping,
+    Nonce(Nonce),
+    Transfer(String, u64),
+    Status { version: u32, network: u64 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Envelope {
+    nonce: Nonce,
+    signature: Signature,
+    messages: Vec<Message>,
+}
+
+#[test]
+fn newtype_struct() {
+    roundtrip(&Nonce(42));
+    roundtrip(&vec![Nonce(1), Nonce(2)]);
+}
+
+#[test]
+fn tuple_struct() {
+    roundtrip(&Signature(27, "r".to_string(), "s".to_string()));
+    roundtrip(&vec![
+        Signature(27, "r".to_string(), "s".to_string()),
+        Signature(28, "rr".to_string(), "ss".to_string()),
+    ]);
+}
+
+#[test]
+fn enum_variants() {
+    roundtrip(&Message::Ping);
+    roundtrip(&Message::Nonce(Nonce(1)));
+    roundtrip(&Message::Transfer("alice".to_string(), 1000));
+    roundtrip(&Message::Status {
+        version: 63,
+        network: 1,
+    });
+}
+
+#[test]
+fn map() {
+    let mut map = BTreeMap::new();
+    map.insert("alice".to_string(), 10u64);
+    map.insert("bob".to_string(), 20u64);
+    roundtrip(&map);
+    roundtrip(&BTreeMap::<String, u64>::new());
+}
+
+#[test]
+fn nested_compound_types() {
+    roundtrip(&Envelope {
+        nonce: Nonce(7),
+        signature: Signature(27, "r".to_string(), "s".to_string()),
+        messages: vec![
+            Message::Ping,
+            Message::Transfer("alice".to_string(), 1000),
+            Message::Status {
+                version: 63,
+                network: 1,
+            },
+            Message::Ping,
+        ],
+    });
+}
+
+#[test]
+fn enum_tag_mismatch() {
+    let bytes = Serializer::new()
+        .variant_tag(VariantTag::Index)
+        .to_bytes(&Message::Ping)
+        .unwrap();
+    assert!(from_bytes::<Message>(&bytes).is_err());
+    // Index out of range
+    let mut deserializer = Deserializer::with_options(
+        &[0xc1, 0x09],
+        DecodeOptions::new().variant_tag(VariantTag::Index),
+    );
+    assert!(Message::deserialize(&mut deserializer).is_err());
+}
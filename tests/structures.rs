@@ -1,26 +1,44 @@
+extern crate serde;
 extern crate serde_rlp;
 #[macro_use]
 extern crate serde_derive;
 
-use serde_rlp::ser::to_bytes;
+use serde::{Deserialize, Serialize};
+use serde_rlp::config::StructEncoding;
+use serde_rlp::de::{from_bytes, DecodeOptions, Deserializer};
+use serde_rlp::ser::{to_bytes, Serializer};
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 struct Person {
     first_name: String,
     last_name: String,
     age: u64,
 }
 
-#[test]
-fn serialize() {
-    let person = Person {
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Family {
+    name: String,
+    head: Person,
+    members: Vec<Person>,
+}
+
+fn john_doe() -> Person {
+    Person {
         first_name: "John".to_string(),
         last_name: "Doe".to_string(),
         age: 42u64,
-    };
+    }
+}
+
+#[test]
+fn serialize() {
+    let person = john_doe();
     // Generated with pyrlp with [["first_name", "John"], ["last_name", "Doe"], ["age", 42]]
     assert_eq!(
-        to_bytes(&person).unwrap(),
+        Serializer::new()
+            .struct_encoding(StructEncoding::KeyValue)
+            .to_bytes(&person)
+            .unwrap(),
         vec![
             0xe6, 0xd0, 0x8a, 0x66, 0x69, 0x72, 0x73, 0x74, 0x5f, 0x6e, 0x61, 0x6d, 0x65, 0x84,
             0x4a, 0x6f, 0x68, 0x6e, 0xce, 0x89, 0x6c, 0x61, 0x73, 0x74, 0x5f, 0x6e, 0x61, 0x6d,
@@ -28,3 +46,143 @@ fn serialize() {
         ]
     );
 }
+
+#[test]
+fn serialize_as_list() {
+    // Generated with pyrlp with ["John", "Doe", 42]
+    assert_eq!(
+        to_bytes(&john_doe()).unwrap(),
+        vec![0xca, 0x84, 0x4a, 0x6f, 0x68, 0x6e, 0x83, 0x44, 0x6f, 0x65, 0x2a]
+    );
+}
+
+#[test]
+fn deserialize_from_list() {
+    let person: Person = from_bytes(&[
+        0xca, 0x84, 0x4a, 0x6f, 0x68, 0x6e, 0x83, 0x44, 0x6f, 0x65, 0x2a,
+    ])
+    .unwrap();
+    assert_eq!(person, john_doe());
+}
+
+fn doe_family() -> Family {
+    Family {
+        name: "Doe".to_string(),
+        head: john_doe(),
+        members: vec![
+            Person {
+                first_name: "Jane".to_string(),
+                last_name: "Doe".to_string(),
+                age: 40u64,
+            },
+            Person {
+                first_name: "Baby".to_string(),
+                last_name: "Doe".to_string(),
+                age: 0u64,
+            },
+        ],
+    }
+}
+
+fn roundtrip_with<T>(value: &T, encoding: StructEncoding) -> T
+where
+    T: Serialize + for<'de> Deserialize<'de>,
+{
+    let bytes = Serializer::new()
+        .struct_encoding(encoding)
+        .to_bytes(value)
+        .unwrap();
+    let mut deserializer =
+        Deserializer::with_options(&bytes, DecodeOptions::new().struct_encoding(encoding));
+    let decoded = T::deserialize(&mut deserializer).unwrap();
+    deserializer.end().unwrap();
+    decoded
+}
+
+#[test]
+fn deserialize_from_key_value() {
+    let data = [
+        0xe6, 0xd0, 0x8a, 0x66, 0x69, 0x72, 0x73, 0x74, 0x5f, 0x6e, 0x61, 0x6d, 0x65, 0x84, 0x4a,
+        0x6f, 0x68, 0x6e, 0xce, 0x89, 0x6c, 0x61, 0x73, 0x74, 0x5f, 0x6e, 0x61, 0x6d, 0x65, 0x83,
+        0x44, 0x6f, 0x65, 0xc5, 0x83, 0x61, 0x67, 0x65, 0x2a,
+    ];
+    let mut deserializer = Deserializer::with_options(
+        &data,
+        DecodeOptions::new().struct_encoding(StructEncoding::KeyValue),
+    );
+    assert_eq!(Person::deserialize(&mut deserializer).unwrap(), john_doe());
+}
+
+#[test]
+fn deserialize_from_key_value_in_any_order() {
+    // [["age", 42], ["last_name", "Doe"], ["first_name", "John"]]
+    let data = [
+        0xe6, 0xc5, 0x83, 0x61, 0x67, 0x65, 0x2a, 0xce, 0x89, 0x6c, 0x61, 0x73, 0x74, 0x5f, 0x6e,
+        0x61, 0x6d, 0x65, 0x83, 0x44, 0x6f, 0x65, 0xd0, 0x8a, 0x66, 0x69, 0x72, 0x73, 0x74, 0x5f,
+        0x6e, 0x61, 0x6d, 0x65, 0x84, 0x4a, 0x6f, 0x68, 0x6e,
+    ];
+    let mut deserializer = Deserializer::with_options(
+        &data,
+        DecodeOptions::new().struct_encoding(StructEncoding::KeyValue),
+    );
+    assert_eq!(Person::deserialize(&mut deserializer).unwrap(), john_doe());
+}
+
+#[test]
+fn roundtrip_person() {
+    assert_eq!(
+        roundtrip_with(&john_doe(), StructEncoding::List),
+        john_doe()
+    );
+    assert_eq!(
+        roundtrip_with(&john_doe(), StructEncoding::KeyValue),
+        john_doe()
+    );
+}
+
+#[test]
+fn roundtrip_nested_structs() {
+    assert_eq!(
+        roundtrip_with(&doe_family(), StructEncoding::List),
+        doe_family()
+    );
+    assert_eq!(
+        roundtrip_with(&doe_family(), StructEncoding::KeyValue),
+        doe_family()
+    );
+}
+
+#[test]
+fn deserialize_struct_with_missing_field() {
+    // ["John", "Doe"]
+    assert!(
+        from_bytes::<Person>(&[0xc9, 0x84, 0x4a, 0x6f, 0x68, 0x6e, 0x83, 0x44, 0x6f, 0x65])
+            .is_err()
+    );
+}
+
+#[test]
+fn deserialize_from_key_value_skips_unknown_fields() {
+    #[derive(Serialize)]
+    struct PersonWithPets {
+        first_name: String,
+        pets: Vec<Vec<String>>,
+        last_name: String,
+        age: u64,
+    }
+    let person = PersonWithPets {
+        first_name: "John".to_string(),
+        pets: vec![vec!["Rex".to_string(), "dog".to_string()]],
+        last_name: "Doe".to_string(),
+        age: 42,
+    };
+    let data = Serializer::new()
+        .struct_encoding(StructEncoding::KeyValue)
+        .to_bytes(&person)
+        .unwrap();
+    let mut deserializer = Deserializer::with_options(
+        &data,
+        DecodeOptions::new().struct_encoding(StructEncoding::KeyValue),
+    );
+    assert_eq!(Person::deserialize(&mut deserializer).unwrap(), john_doe());
+}